@@ -1,11 +1,20 @@
+use crate::data::effects::Effects;
 use crate::data::machines::{
-    BeaconPrototype, CraftingMachinePrototype, MiningDrillPrototype, ModulePrototype,
-    RecipePrototype,
+    BeaconPrototype, CraftingMachinePrototype, EnergySource, GeneratorPrototype, LabPrototype,
+    Machine, MiningDrillPrototype, ModulePrototype, RecipePrototype,
 };
-use crate::data::materials::{FluidPrototype, Item, ItemPrototype, Material};
+use crate::data::materials::{
+    Fluid, FluidPrototype, Item, ItemPrototype, Material, MaterialPrototype,
+};
+use crate::data::quality::{get_quality_distribution, QualityPrototype, NORMAL_QUALITY};
 use crate::data::resources::{PlantPrototype, ResourcePrototype};
+use crate::data::surfaces::{
+    check_surface_conditions, get_surface_property, SurfaceCondition, SurfacePrototype,
+};
+use crate::data::technologies::{ProductivityBonuses, TechnologyPrototype, TechnologyUnit};
+use crate::model::limit::Limit;
 use lazy_static::lazy_static;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::sync::{Arc, RwLock};
@@ -14,7 +23,10 @@ pub mod data_loader;
 pub mod effects;
 pub mod machines;
 pub mod materials;
+pub mod quality;
 pub mod resources;
+pub mod surfaces;
+pub mod technologies;
 pub mod types;
 
 lazy_static! {
@@ -29,6 +41,18 @@ pub fn set_registry(registry: Registry) {
     REGISTRY.write().unwrap().replace(Arc::new(registry));
 }
 
+/// Sets the registry parsed from `data` for a test. Tests sharing the global registry run in
+/// parallel, so the registry stays theirs until the returned guard is dropped.
+#[cfg(test)]
+pub fn set_test_registry(data: &str) -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let (registry, warnings) = data_loader::parse_data(data).unwrap();
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    set_registry(registry);
+    guard
+}
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Registry {
     pub items: HashMap<String, ItemPrototype>,
@@ -37,9 +61,16 @@ pub struct Registry {
     pub plants: HashMap<String, PlantPrototype>,
     pub mining_drills: HashMap<String, MiningDrillPrototype>,
     pub crafting_machines: HashMap<String, CraftingMachinePrototype>,
+    pub labs: HashMap<String, LabPrototype>,
     pub recipes: HashMap<String, RecipePrototype>,
     pub modules: HashMap<String, ModulePrototype>,
     pub beacons: HashMap<String, BeaconPrototype>,
+    pub generators: HashMap<String, GeneratorPrototype>,
+    pub qualities: HashMap<String, QualityPrototype>,
+    pub surfaces: HashMap<String, SurfacePrototype>,
+    /// The default value of each surface property.
+    pub surface_properties: HashMap<String, f64>,
+    pub technologies: HashMap<String, TechnologyPrototype>,
     // pub processes: Vec<Process>,
 }
 
@@ -48,14 +79,22 @@ pub enum ProcessType {
     Resource,
     Plant,
     Recipe,
+    Generator,
+    /// Researching a technology in labs, one research unit per craft.
+    Research,
+    /// Destroying one unit of the material named by its id per craft, like an incinerator.
+    Void,
 }
 
-impl Into<String> for &ProcessType {
-    fn into(self) -> String {
-        match self {
+impl From<&ProcessType> for String {
+    fn from(process_type: &ProcessType) -> Self {
+        match process_type {
             ProcessType::Resource => "Resource",
             ProcessType::Plant => "Plant",
             ProcessType::Recipe => "Recipe",
+            ProcessType::Generator => "Generator",
+            ProcessType::Research => "Research",
+            ProcessType::Void => "Void",
         }
         .into()
     }
@@ -68,35 +107,498 @@ impl TryFrom<&String> for ProcessType {
             "Resource" => Ok(ProcessType::Resource),
             "Plant" => Ok(ProcessType::Plant),
             "Recipe" => Ok(ProcessType::Recipe),
+            "Generator" => Ok(ProcessType::Generator),
+            "Research" => Ok(ProcessType::Research),
+            "Void" => Ok(ProcessType::Void),
             _ => Err("Unknown process type"),
         }
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Beacon {
+    pub prototype: String,
+    pub count: u16,
+    pub modules: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Process {
     pub process_type: ProcessType,
     pub name: String,
-    pub productivity: f32,
+    /// The quality of the item ingredients, and the lowest quality of the item products.
+    pub quality: String,
+    pub machine: Option<String>,
+    pub modules: Vec<String>,
+    pub beacons: HashMap<String, Beacon>,
+    /// The item burnt by burner machines.
+    #[serde(default)]
+    pub fuel: Option<String>,
+    /// Tells apart instances of the same process set up differently.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// The number of machines the process may run in, when limited.
+    #[serde(default, deserialize_with = "deserialize_machine_limit")]
+    pub machine_limit: Option<Limit>,
+}
+
+/// A machine limit as saved: a limit, or the exact machine count of processes saved before
+/// their machine count could be a range or a bound.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedMachineLimit {
+    Limit(Limit),
+    FixedMachines(f64),
+}
+
+fn deserialize_machine_limit<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Limit>, D::Error> {
+    Ok(
+        Option::<SavedMachineLimit>::deserialize(deserializer)?.map(|limit| match limit {
+            SavedMachineLimit::Limit(limit) => limit,
+            SavedMachineLimit::FixedMachines(count) => Limit::Exact(count),
+        }),
+    )
 }
 
 impl Hash for Process {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.name.hash(state);
+        self.quality.hash(state);
+        self.label.hash(state);
     }
 }
 
 impl PartialEq for Process {
     fn eq(&self, other: &Self) -> bool {
-        self.process_type == other.process_type && self.name == other.name
+        self.process_type == other.process_type
+            && self.name == other.name
+            && self.quality == other.quality
+            && self.label == other.label
     }
 }
 
 impl Eq for Process {}
 
 impl Process {
-    pub fn get_ingredients(&self) -> Vec<Material> {
+    pub fn new(process_type: ProcessType, name: String) -> Self {
+        Process {
+            process_type,
+            name,
+            quality: NORMAL_QUALITY.into(),
+            machine: None,
+            modules: vec![],
+            beacons: HashMap::new(),
+            fuel: None,
+            label: None,
+            machine_limit: None,
+        }
+    }
+
+    pub fn get_machine<'a>(&self, registry: &'a Registry) -> Option<Machine<'a>> {
+        let machine = self.machine.as_ref()?;
+        match self.process_type {
+            ProcessType::Resource => registry
+                .mining_drills
+                .get(machine)
+                .map(Machine::MiningDrill),
+            ProcessType::Plant | ProcessType::Generator | ProcessType::Void => None,
+            ProcessType::Recipe => registry
+                .crafting_machines
+                .get(machine)
+                .map(Machine::CraftingMachine),
+            ProcessType::Research => registry.labs.get(machine).map(Machine::Lab),
+        }
+    }
+
+    /// Returns the machines that can run the process on `surface`. Plants, generators and void
+    /// processes run without one.
+    pub fn get_machine_choices(&self, surface: Option<&str>) -> Vec<String> {
+        let registry = get_registry();
+        match self.process_type {
+            ProcessType::Resource => {
+                let category = &registry.resources.get(&self.name).unwrap().category;
+                registry
+                    .mining_drills
+                    .iter()
+                    .filter(|(_, drill)| {
+                        drill.resource_categories.contains(category)
+                            && check_surface_conditions(
+                                &registry,
+                                &drill.surface_conditions,
+                                surface,
+                            )
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            }
+            ProcessType::Plant | ProcessType::Generator | ProcessType::Void => vec![],
+            ProcessType::Recipe => {
+                let category = &registry.recipes.get(&self.name).unwrap().category;
+                registry
+                    .crafting_machines
+                    .iter()
+                    .filter(|(_, machine)| {
+                        machine.crafting_categories.contains(category)
+                            && check_surface_conditions(
+                                &registry,
+                                &machine.surface_conditions,
+                                surface,
+                            )
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            }
+            ProcessType::Research => {
+                let packs: Vec<String> = self
+                    .get_ingredients()
+                    .iter()
+                    .filter_map(|pack| match pack.get_prototype() {
+                        MaterialPrototype::Item(name, _) => Some(name),
+                        _ => None,
+                    })
+                    .collect();
+                registry
+                    .labs
+                    .iter()
+                    .filter(|(_, lab)| {
+                        packs.iter().all(|pack| lab.inputs.contains(pack))
+                            && check_surface_conditions(&registry, &lab.surface_conditions, surface)
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns whether the process and its machine meet the surface conditions of `surface`.
+    pub fn can_run_on(&self, surface: Option<&str>) -> bool {
+        let registry = get_registry();
+        let conditions: &[SurfaceCondition] = match self.process_type {
+            ProcessType::Plant => &registry.plants.get(&self.name).unwrap().surface_conditions,
+            ProcessType::Recipe => &registry.recipes.get(&self.name).unwrap().surface_conditions,
+            ProcessType::Resource
+            | ProcessType::Generator
+            | ProcessType::Research
+            | ProcessType::Void => &[],
+        };
+        check_surface_conditions(&registry, conditions, surface)
+            && self.get_machine(&registry).is_none_or(|machine| {
+                check_surface_conditions(&registry, machine.get_surface_conditions(), surface)
+            })
+    }
+
+    /// Sums the effects of the machine, its modules, its beacons and the surface, keeping only
+    /// the effects both the machine and the recipe allow.
+    pub fn get_effects(&self, surface: Option<&str>) -> Effects {
+        let registry = get_registry();
+        let Some(machine) = self.get_machine(&registry) else {
+            return Effects::default();
+        };
+        let effect_receiver = machine.get_effect_receiver();
+        let mut effects = effect_receiver.base_effect.unwrap_or_default();
+        if effect_receiver.uses_module_effects {
+            for module in self.modules.iter() {
+                effects.add(&registry.modules.get(module).unwrap().effects, 1.0);
+            }
+        }
+        if effect_receiver.uses_beacon_effects {
+            let total_count: u16 = self.beacons.values().map(|beacon| beacon.count).sum();
+            for beacon in self.beacons.values() {
+                let prototype = registry.beacons.get(&beacon.prototype).unwrap();
+                let count = if prototype.beacon_counter == Some("same_type".into()) {
+                    beacon.count
+                } else {
+                    total_count
+                };
+                let multiplier = prototype.efficiency
+                    * prototype.profile.as_ref().map_or(1.0, |profile| {
+                        *profile
+                            .get((count as usize).saturating_sub(1))
+                            .unwrap_or(profile.last().unwrap_or(&1.0))
+                    })
+                    * beacon.count as f64;
+                for module in beacon.modules.iter() {
+                    effects.add(
+                        &registry.modules.get(module).unwrap().effects,
+                        multiplier as f32,
+                    );
+                }
+            }
+        }
+        if effect_receiver.uses_surface_effects
+            && let Some(surface) = surface.and_then(|surface| registry.surfaces.get(surface))
+        {
+            effects.add(&surface.effects, 1.0);
+        }
+        effects.retain(machine.get_allowed_effects());
+        if self.process_type == ProcessType::Recipe {
+            effects.retain(&registry.recipes.get(&self.name).unwrap().allowed_effects);
+        }
+        effects.clamp();
+        effects
+    }
+
+    /// Returns the duration of one craft at speed 1.
+    pub fn get_time(&self) -> f64 {
+        let registry = get_registry();
+        match self.process_type {
+            ProcessType::Resource => {
+                registry
+                    .resources
+                    .get(&self.name)
+                    .unwrap()
+                    .results
+                    .mining_time
+            }
+            ProcessType::Plant => {
+                let plant = registry.plants.get(&self.name).unwrap();
+                plant.growth_ticks as f64 / 60.0 + plant.results.mining_time
+            }
+            ProcessType::Recipe => registry.recipes.get(&self.name).unwrap().energy_required,
+            ProcessType::Generator | ProcessType::Void => 1.0,
+            ProcessType::Research => self.get_research_unit(&registry).time,
+        }
+    }
+
+    /// Returns the cost of the technology a research process researches.
+    fn get_research_unit<'a>(&self, registry: &'a Registry) -> &'a TechnologyUnit {
+        registry
+            .technologies
+            .get(&self.name)
+            .unwrap()
+            .unit
+            .as_ref()
+            .unwrap()
+    }
+
+    /// Returns the speed of the machine with its speed effects applied. Processes without a
+    /// machine, like plants, run at speed 1.
+    pub fn get_speed(&self, surface: Option<&str>) -> f64 {
+        self.get_machine(&get_registry()).map_or(1.0, |machine| {
+            machine.get_speed() * (1.0 + self.get_effects(surface).speed.unwrap_or(0.0) as f64)
+        })
+    }
+
+    /// Returns how many machines it takes to run the process `crafts` times per second.
+    pub fn get_machine_count(&self, crafts: f64, surface: Option<&str>) -> f64 {
+        crafts * self.get_time() / self.get_speed(surface)
+    }
+
+    /// Returns the productivity of the process, research bonuses included, capped by the
+    /// maximum productivity of its recipe.
+    pub fn get_productivity(&self, surface: Option<&str>, bonuses: &ProductivityBonuses) -> f64 {
+        let productivity =
+            self.get_effects(surface).productivity.unwrap_or(0.0) as f64 + bonuses.get(self);
         match self.process_type {
+            ProcessType::Recipe => productivity.min(
+                get_registry()
+                    .recipes
+                    .get(&self.name)
+                    .unwrap()
+                    .maximum_productivity,
+            ),
+            _ => productivity,
+        }
+    }
+
+    /// Returns the average amount of each product per craft, with productivity applied and
+    /// item products spread over the qualities the quality effect can reach.
+    pub fn get_product_amounts(
+        &self,
+        surface: Option<&str>,
+        bonuses: &ProductivityBonuses,
+    ) -> Vec<(MaterialPrototype, f64)> {
+        let effects = self.get_effects(surface);
+        let productivity = self.get_productivity(surface, bonuses);
+        if self.process_type == ProcessType::Research {
+            return vec![(
+                MaterialPrototype::Research(self.name.clone()),
+                1.0 + productivity,
+            )];
+        }
+        let distribution = get_quality_distribution(
+            &get_registry(),
+            &self.quality,
+            effects.quality.unwrap_or(0.0),
+        );
+        self.get_products()
+            .into_iter()
+            .flat_map(|product| {
+                let amount = product.get_average_amount(productivity);
+                match product {
+                    Material::Item(item) => distribution
+                        .iter()
+                        .map(|(quality, share)| {
+                            (
+                                MaterialPrototype::Item(item.name.clone(), quality.clone()),
+                                amount * share,
+                            )
+                        })
+                        .collect(),
+                    Material::Fluid(_) => vec![(product.get_prototype(), amount)],
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the power the process uses while working in W, before consumption effects,
+    /// and the energy source providing it.
+    fn get_energy_usage(&self, registry: &Registry) -> Option<(f64, EnergySource)> {
+        if self.process_type == ProcessType::Generator {
+            let generator = registry.generators.get(&self.name).unwrap();
+            return Some((generator.energy_usage.0, generator.energy_source.clone()));
+        }
+        let machine = self.get_machine(registry)?;
+        Some((
+            machine.get_energy_usage().0,
+            machine.get_energy_source().clone(),
+        ))
+    }
+
+    /// Returns the energy used per craft while working, in J.
+    fn get_working_energy(&self, energy_usage: f64, surface: Option<&str>) -> f64 {
+        energy_usage
+            * (1.0 + self.get_effects(surface).consumption.unwrap_or(0.0) as f64)
+            * self.get_time()
+            / self.get_speed(surface)
+    }
+
+    /// Returns the fuel categories the process can burn, if it runs on a burner.
+    pub fn get_fuel_categories(&self) -> Option<Vec<String>> {
+        match self.get_energy_usage(&get_registry()) {
+            Some((
+                _,
+                EnergySource::Burner {
+                    fuel_categories, ..
+                },
+            )) => Some(fuel_categories),
+            _ => None,
+        }
+    }
+
+    /// Returns the electric energy made per craft in MJ, negative when the process uses it.
+    pub fn get_electricity(&self, surface: Option<&str>) -> f64 {
+        let registry = get_registry();
+        let power = match self.process_type {
+            ProcessType::Generator => {
+                let generator = registry.generators.get(&self.name).unwrap();
+                match surface {
+                    Some(surface) if generator.solar => {
+                        generator.power.to_mega()
+                            * get_surface_property(&registry, surface, "solar-power")
+                            / 100.0
+                    }
+                    _ => generator.power.to_mega(),
+                }
+            }
+            _ => 0.0,
+        };
+        let Some((energy_usage, EnergySource::Electric { drain })) =
+            self.get_energy_usage(&registry)
+        else {
+            return power;
+        };
+        let drain = drain.map_or(energy_usage / 30.0, |drain| drain.0);
+        power
+            - (self.get_working_energy(energy_usage, surface)
+                + drain * self.get_time() / self.get_speed(surface))
+                / 1e6
+    }
+
+    /// Returns the fuel burnt per craft as a negative amount, and the burnt result it leaves.
+    pub fn get_fuel_amounts(&self, surface: Option<&str>) -> Vec<(MaterialPrototype, f64)> {
+        let registry = get_registry();
+        let (Some(fuel), Some((energy_usage, EnergySource::Burner { effectivity, .. }))) =
+            (self.fuel.as_ref(), self.get_energy_usage(&registry))
+        else {
+            return vec![];
+        };
+        let Some(item) = registry.items.get(fuel) else {
+            return vec![];
+        };
+        let Some(fuel_value) = item.fuel_value else {
+            return vec![];
+        };
+        let amount = self.get_working_energy(energy_usage, surface) / (fuel_value.0 * effectivity);
+        let mut amounts = vec![(
+            MaterialPrototype::Item(fuel.clone(), NORMAL_QUALITY.into()),
+            -amount,
+        )];
+        if let Some(burnt_result) = item.burnt_result.as_ref().filter(|name| !name.is_empty()) {
+            amounts.push((
+                MaterialPrototype::Item(burnt_result.clone(), NORMAL_QUALITY.into()),
+                amount,
+            ));
+        }
+        amounts
+    }
+
+    /// Returns the emissions of each pollutant per craft, with consumption and pollution
+    /// effects applied.
+    pub fn get_emissions(&self, surface: Option<&str>) -> HashMap<String, f64> {
+        let registry = get_registry();
+        let emissions_per_minute = match self.process_type {
+            ProcessType::Generator => {
+                &registry
+                    .generators
+                    .get(&self.name)
+                    .unwrap()
+                    .emissions_per_minute
+            }
+            _ => match self.get_machine(&registry) {
+                Some(machine) => machine.get_emissions_per_minute(),
+                None => return HashMap::new(),
+            },
+        };
+        let effects = self.get_effects(surface);
+        let mut multiplier = (1.0 + effects.consumption.unwrap_or(0.0) as f64)
+            * (1.0 + effects.pollution.unwrap_or(0.0) as f64)
+            * self.get_time()
+            / self.get_speed(surface)
+            / 60.0;
+        if self.process_type == ProcessType::Recipe {
+            multiplier *= registry
+                .recipes
+                .get(&self.name)
+                .unwrap()
+                .emissions_multiplier;
+        }
+        emissions_per_minute
+            .iter()
+            .map(|(pollutant, amount)| (pollutant.clone(), amount * multiplier))
+            .collect()
+    }
+
+    /// Returns the average net amount of each material per craft: products with productivity
+    /// applied, minus ingredients. Catalysts, which are both, only count the difference, and
+    /// productivity only adds to the part not ignored by it.
+    pub fn get_net_amounts(
+        &self,
+        surface: Option<&str>,
+        bonuses: &ProductivityBonuses,
+    ) -> HashMap<MaterialPrototype, f64> {
+        let mut amounts: HashMap<MaterialPrototype, f64> = HashMap::new();
+        for ingredient in self.get_ingredients() {
+            *amounts.entry(ingredient.get_prototype()).or_default() -=
+                ingredient.get_average_amount(0.0);
+        }
+        for (product, amount) in self.get_product_amounts(surface, bonuses) {
+            *amounts.entry(product).or_default() += amount;
+        }
+        for (material, amount) in self.get_fuel_amounts(surface) {
+            *amounts.entry(material).or_default() += amount;
+        }
+        let electricity = self.get_electricity(surface);
+        if electricity != 0.0 {
+            amounts.insert(MaterialPrototype::Electricity, electricity);
+        }
+        amounts
+    }
+
+    pub fn get_ingredients(&self) -> Vec<Material> {
+        self.with_quality(match self.process_type {
             ProcessType::Resource => get_registry()
                 .resources
                 .get(&self.name)
@@ -105,7 +607,7 @@ impl Process {
                 .input_fluid
                 .clone()
                 .map(|fluid| vec![Material::Fluid(fluid)])
-                .unwrap_or_else(|| vec![]),
+                .unwrap_or_default(),
             ProcessType::Plant => get_registry()
                 .plants
                 .get(&self.name)
@@ -131,11 +633,65 @@ impl Process {
                 .unwrap()
                 .ingredients
                 .clone(),
-        }
+            ProcessType::Generator => get_registry()
+                .generators
+                .get(&self.name)
+                .unwrap()
+                .ingredients
+                .clone(),
+            ProcessType::Research => {
+                let registry = get_registry();
+                // Labs draining less than a whole pack per unit use it up over several units.
+                let drain = self
+                    .get_machine(&registry)
+                    .map_or(100, |machine| match machine {
+                        Machine::Lab(lab) => lab.science_pack_drain_rate_percent,
+                        _ => 100,
+                    });
+                self.get_research_unit(&registry)
+                    .ingredients
+                    .iter()
+                    .map(|(name, amount)| {
+                        Material::Item(Item {
+                            name: name.clone(),
+                            quality: None,
+                            amount: Some(*amount as u16),
+                            amount_min: None,
+                            amount_max: None,
+                            probability: (drain != 100).then_some(drain as f64 / 100.0),
+                            ignored_by_productivity: None,
+                            extra_count_fraction: None,
+                        })
+                    })
+                    .collect()
+            }
+            ProcessType::Void => match MaterialPrototype::from_id(&self.name) {
+                Ok(MaterialPrototype::Item(name, quality)) => vec![Material::Item(Item {
+                    name,
+                    quality: Some(quality),
+                    amount: Some(1),
+                    amount_min: None,
+                    amount_max: None,
+                    probability: None,
+                    ignored_by_productivity: None,
+                    extra_count_fraction: None,
+                })],
+                Ok(MaterialPrototype::Fluid(name)) => vec![Material::Fluid(Fluid {
+                    name,
+                    temperature: None,
+                    amount: Some(1.0),
+                    amount_min: None,
+                    amount_max: None,
+                    probability: None,
+                    ignored_by_productivity: None,
+                })],
+                _ => vec![],
+            },
+        })
     }
 
     pub fn get_products(&self) -> Vec<Material> {
-        match self.process_type {
+        self.with_quality(match self.process_type {
             ProcessType::Resource => get_registry()
                 .resources
                 .get(&self.name)
@@ -156,6 +712,28 @@ impl Process {
                 .unwrap()
                 .results
                 .clone(),
-        }
+            ProcessType::Generator => get_registry()
+                .generators
+                .get(&self.name)
+                .unwrap()
+                .results
+                .clone(),
+            // Research units aren't materials, see `get_product_amounts`.
+            ProcessType::Research | ProcessType::Void => vec![],
+        })
+    }
+
+    /// Gives the items without an explicit quality the quality of the process.
+    fn with_quality(&self, materials: Vec<Material>) -> Vec<Material> {
+        materials
+            .into_iter()
+            .map(|material| match material {
+                Material::Item(item) if item.quality.is_none() => Material::Item(Item {
+                    quality: Some(self.quality.clone()),
+                    ..item
+                }),
+                material => material,
+            })
+            .collect()
     }
 }
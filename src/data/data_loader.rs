@@ -1,24 +1,115 @@
 use crate::data::effects::{EffectReceiver, Effects};
 use crate::data::machines::{
-    BeaconPrototype, CraftingMachinePrototype, EnergySource, MiningDrillPrototype, ModulePrototype,
-    RecipePrototype,
+    BeaconPrototype, CraftingMachinePrototype, EnergySource, GeneratorPrototype, LabPrototype,
+    MiningDrillPrototype, ModulePrototype, RecipePrototype,
 };
 use crate::data::materials::{Fluid, FluidPrototype, Item, ItemPrototype, Material};
-use crate::data::resources::{Minable, PlantPrototype, ResourcePrototype};
+use crate::data::quality::QualityPrototype;
+use crate::data::resources::{Minable, PlantPrototype, ResourcePrototype, TILE_CATEGORY};
+use crate::data::surfaces::{SurfaceCondition, SurfacePrototype};
+use crate::data::technologies::{TechnologyEffect, TechnologyPrototype, TechnologyUnit};
+use crate::data::types::Energy;
 use crate::data::Registry;
-use json::{Error, JsonValue};
+use json::JsonValue;
 use std::collections::HashMap;
-use std::fs;
+use std::fmt::{Display, Formatter};
 use std::string::String;
+use std::{fs, io};
 
-pub fn load_data(dump: String) -> Result<Registry, Error> {
-    let mut registry: Registry = Registry::default();
+/// An error raised while loading a data dump.
+///
+/// `Io` and `Json` are fatal. `Prototype` errors are collected as warnings and loading
+/// continues: the offending prototype is skipped, or for a plant result only the link from
+/// the seed to its plant.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Json(json::Error),
+    Prototype {
+        category: String,
+        name: String,
+        path: String,
+        reason: String,
+    },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "Failed to read data file: {}", error),
+            LoadError::Json(error) => write!(f, "Failed to parse data file: {}", error),
+            LoadError::Prototype {
+                category,
+                name,
+                path,
+                reason,
+            } => write!(f, "{} '{}' at {}: {}", category, name, path, reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+impl From<json::Error> for LoadError {
+    fn from(error: json::Error) -> Self {
+        LoadError::Json(error)
+    }
+}
+
+/// A problem with a single field of a prototype, before the prototype it belongs to is known.
+struct FieldError {
+    path: String,
+    reason: String,
+}
+
+impl FieldError {
+    fn new<S: Into<String>>(path: &str, reason: S) -> Self {
+        FieldError {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Prefixes the path with the field that contains it.
+    fn within(self, parent: &str) -> Self {
+        FieldError {
+            path: if self.path.starts_with('[') {
+                format!("{}{}", parent, self.path)
+            } else {
+                format!("{}.{}", parent, self.path)
+            },
+            reason: self.reason,
+        }
+    }
+
+    fn into_load_error(self, category: &str, name: &str) -> LoadError {
+        LoadError::Prototype {
+            category: category.into(),
+            name: name.into(),
+            path: self.path,
+            reason: self.reason,
+        }
+    }
+}
+
+pub fn load_data(dump: String) -> Result<(Registry, Vec<LoadError>), LoadError> {
+    let data: String = fs::read_to_string(dump)?;
+    parse_data(&data)
+}
 
-    let data: String = fs::read_to_string(dump).unwrap();
+pub fn parse_data(data: &str) -> Result<(Registry, Vec<LoadError>), LoadError> {
+    let mut registry: Registry = Registry::default();
+    let mut warnings: Vec<LoadError> = vec![];
 
-    let parsed: JsonValue = json::parse(&data)?;
+    let parsed: JsonValue = json::parse(data)?;
 
-    for prototype in vec![
+    for prototype in [
         "item",
         "ammo",
         "capsule",
@@ -29,63 +120,64 @@ pub fn load_data(dump: String) -> Result<Registry, Error> {
         "armor",
         "repair-tool",
     ] {
-        for (name, value) in parsed[prototype].entries() {
-            registry.items.insert(
-                name.into(),
-                ItemPrototype {
-                    name: value["name"].as_str().unwrap().into(),
-                    stack_size: value["stack_size"].as_u32().unwrap(),
+        registry.items.extend(load_prototypes(
+            &parsed,
+            prototype,
+            &mut warnings,
+            |value| {
+                Ok(ItemPrototype {
+                    name: get_string(value, "name")?,
+                    stack_size: get_u32(value, "stack_size")?,
                     fuel_category: value["fuel_category"].as_str().map(|s| s.into()),
-                    fuel_value: value["fuel_value"].as_u32(),
+                    fuel_value: get_fuel_value(value)?,
                     burnt_result: value["burnt_result"].as_str().map(|s| s.into()),
                     spoil_result: value["spoil_result"].as_str().map(|s| s.into()),
                     plant_result: value["plant_result"].as_str().map(|s| s.into()),
-                },
-            );
-        }
-    }
-
-    for (name, value) in parsed["fluid"].entries() {
-        registry.fluids.insert(
-            name.into(),
-            FluidPrototype {
-                name: value["name"].as_str().unwrap().into(),
-                fuel_value: value["fuel_value"].as_u32(),
+                })
             },
-        );
+        ));
     }
 
-    for (name, value) in parsed["resource"].entries() {
-        registry.resources.insert(
-            name.into(),
-            ResourcePrototype {
-                name: value["name"].as_str().unwrap().into(),
-                category: value["category"]
-                    .as_str()
-                    .or(Some("basic-solid"))
-                    .unwrap()
-                    .into(),
-                results: get_minable(&value["minable"]),
-            },
-        );
-        // let process = Process::Resource {
-        //     name: name.into(),
-        //     productivity: 0.0,
-        // };
-        // registry.processes.push(process);
-    }
+    registry
+        .fluids
+        .extend(load_prototypes(&parsed, "fluid", &mut warnings, |value| {
+            Ok(FluidPrototype {
+                name: get_string(value, "name")?,
+                fuel_value: get_fuel_value(value)?,
+                heat_capacity: if value["heat_capacity"].is_null() {
+                    Energy(1000.0)
+                } else {
+                    get_energy(value, "heat_capacity")?
+                },
+                default_temperature: get_f64(value, "default_temperature")?,
+                max_temperature: value["max_temperature"].as_f64(),
+            })
+        }));
+
+    registry.resources.extend(load_prototypes(
+        &parsed,
+        "resource",
+        &mut warnings,
+        |value| {
+            Ok(ResourcePrototype {
+                name: get_string(value, "name")?,
+                category: value["category"].as_str().unwrap_or("basic-solid").into(),
+                results: get_minable(&value["minable"]).map_err(|e| e.within("minable"))?,
+            })
+        },
+    ));
 
-    for (_name, value) in parsed["tile"].entries() {
-        if value["fluid"].is_string() {
+    for (name, value) in parsed["tile"].entries() {
+        if let Some(fluid) = value["fluid"].as_str() {
             registry.resources.insert(
-                value["fluid"].as_str().unwrap().to_string() + " *tile",
+                fluid.to_string() + " *tile",
                 ResourcePrototype {
-                    name: value["name"].as_str().unwrap().into(),
-                    category: "calculator internal tile".into(),
+                    name: name.into(),
+                    category: TILE_CATEGORY.into(),
                     results: Minable {
                         mining_time: 1.0,
                         results: vec![Material::Fluid(Fluid {
-                            name: value["fluid"].as_str().unwrap().into(),
+                            name: fluid.into(),
                             temperature: None,
                             amount: Some(1.0),
                             amount_min: None,
@@ -100,253 +192,525 @@ pub fn load_data(dump: String) -> Result<Registry, Error> {
         }
     }
 
-    for (name, value) in parsed["plant"].entries() {
-        registry.plants.insert(
-            name.into(),
-            PlantPrototype {
-                name: value["name"].as_str().unwrap().into(),
-                growth_ticks: value["growth_ticks"].as_u32().unwrap(),
-                results: get_minable(&value["minable"]),
+    registry
+        .plants
+        .extend(load_prototypes(&parsed, "plant", &mut warnings, |value| {
+            Ok(PlantPrototype {
+                name: get_string(value, "name")?,
+                growth_ticks: get_u32(value, "growth_ticks")?,
+                results: get_minable(&value["minable"]).map_err(|e| e.within("minable"))?,
                 seeds: vec![],
-            },
-        );
-        // let process = Process::Plant {
-        //     name: name.into(),
-        //     productivity: 0.0,
-        // };
-        // registry.processes.push(process);
-    }
+                surface_conditions: get_surface_conditions(value)?,
+            })
+        }));
 
     let mut plant_seeds: HashMap<String, Vec<String>> = HashMap::new();
 
     for (name, value) in registry.items.iter() {
-        if !value
-            .plant_result
-            .as_ref()
-            .is_some_and(|string: &String| !string.is_empty())
-        {
+        let Some(plant_name) = value.plant_result.as_ref().filter(|name| !name.is_empty()) else {
             continue;
-        }
-        let plant_name = value.plant_result.as_ref().unwrap();
-        if !plant_seeds.contains_key(plant_name.as_str()) {
-            plant_seeds.insert(plant_name.clone(), vec![]);
-        }
+        };
         plant_seeds
-            .get_mut(plant_name.as_str())
-            .as_mut()
-            .unwrap()
+            .entry(plant_name.clone())
+            .or_default()
             .push(name.clone());
     }
 
     for (plant_name, seeds) in plant_seeds {
-        let plant = registry.plants.remove(&plant_name).unwrap();
-        registry.plants.insert(
-            plant_name.clone(),
-            PlantPrototype {
-                name: plant.name,
-                growth_ticks: plant.growth_ticks,
-                results: plant.results,
-                seeds,
-            },
-        );
+        match registry.plants.get_mut(&plant_name) {
+            Some(plant) => plant.seeds = seeds,
+            None => {
+                for seed in seeds {
+                    warnings.push(
+                        FieldError::new(
+                            "plant_result",
+                            format!("unknown or skipped plant '{}'", plant_name),
+                        )
+                        .into_load_error("item", &seed),
+                    );
+                }
+            }
+        }
     }
 
-    for (name, value) in parsed["mining-drill"].entries() {
-        registry.mining_drills.insert(
-            name.into(),
-            MiningDrillPrototype {
-                name: value["name"].as_str().unwrap().into(),
-                energy_usage: get_energy(value["energy_usage"].as_str().unwrap().into()),
-                mining_speed: value["mining_speed"].as_f64().unwrap(),
-                energy_source: get_energy_source(&value["energy_source"]),
-                resource_categories: value["resource_categories"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
+    registry.mining_drills.extend(load_prototypes(
+        &parsed,
+        "mining-drill",
+        &mut warnings,
+        |value| {
+            Ok(MiningDrillPrototype {
+                name: get_string(value, "name")?,
+                energy_usage: get_power(value, "energy_usage")?,
+                mining_speed: get_f64(value, "mining_speed")?,
+                energy_source: get_energy_source(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                emissions_per_minute: get_emissions(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                resource_categories: get_strings(value, "resource_categories")?,
                 effect_receiver: get_effect_receiver(&value["effect_receiver"]),
-                allowed_effects: value["allowed_effects"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
-                allowed_module_categories: value["allowed_module_categories"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
-                module_slots: value["module_slots"].as_u16().or(Some(0)).unwrap(),
+                allowed_effects: get_strings(value, "allowed_effects")?,
+                allowed_module_categories: get_strings(value, "allowed_module_categories")?,
+                module_slots: value["module_slots"].as_u16().unwrap_or(0),
+                surface_conditions: get_surface_conditions(value)?,
                 resource_drain_rate_percent: value["resource_drain_rate_percent"]
                     .as_u8()
-                    .or(Some(100))
-                    .unwrap(),
-            },
-        );
-    }
+                    .unwrap_or(100),
+            })
+        },
+    ));
 
-    for (name, value) in parsed["offshore-pump"].entries() {
-        registry.mining_drills.insert(
-            name.into(),
-            MiningDrillPrototype {
-                name: value["name"].as_str().unwrap().into(),
-                energy_usage: get_energy(value["energy_usage"].as_str().unwrap().into()),
-                mining_speed: value["pumping_speed"].as_f64().unwrap(),
-                energy_source: get_energy_source(&value["energy_source"]),
-                resource_categories: vec![
-                    "calculator internal tile".into(),
-                ],
+    registry.mining_drills.extend(load_prototypes(
+        &parsed,
+        "offshore-pump",
+        &mut warnings,
+        |value| {
+            Ok(MiningDrillPrototype {
+                name: get_string(value, "name")?,
+                energy_usage: get_power(value, "energy_usage")?,
+                mining_speed: get_f64(value, "pumping_speed")?,
+                energy_source: get_energy_source(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                emissions_per_minute: get_emissions(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                resource_categories: vec![TILE_CATEGORY.into()],
                 effect_receiver: None,
                 allowed_effects: vec![],
                 allowed_module_categories: vec!["".into()],
                 module_slots: 0,
                 resource_drain_rate_percent: 0,
-            }
-        );
-    }
+                surface_conditions: get_surface_conditions(value)?,
+            })
+        },
+    ));
 
-    for (name, value) in parsed["assembling-machine"]
-        .entries()
-        .chain(parsed["furnace"].entries())
-    {
-        registry.crafting_machines.insert(
-            name.into(),
-            CraftingMachinePrototype {
-                name: value["name"].as_str().unwrap().into(),
-                energy_usage: get_energy(value["energy_usage"].as_str().unwrap().into()),
-                crafting_speed: value["crafting_speed"].as_f64().unwrap(),
-                crafting_categories: value["crafting_categories"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
-                energy_source: get_energy_source(&value["energy_source"]),
+    registry
+        .labs
+        .extend(load_prototypes(&parsed, "lab", &mut warnings, |value| {
+            Ok(LabPrototype {
+                name: get_string(value, "name")?,
+                energy_usage: get_power(value, "energy_usage")?,
+                researching_speed: value["researching_speed"].as_f64().unwrap_or(1.0),
+                inputs: get_strings(value, "inputs")?,
+                energy_source: get_energy_source(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                emissions_per_minute: get_emissions(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
                 effect_receiver: get_effect_receiver(&value["effect_receiver"]),
-                allowed_effects: value["allowed_effects"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
-                allowed_module_categories: value["allowed_module_categories"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
-                module_slots: value["module_slots"].as_u16().or(Some(0)).unwrap(),
+                allowed_effects: get_strings(value, "allowed_effects")?,
+                allowed_module_categories: get_strings(value, "allowed_module_categories")?,
+                module_slots: value["module_slots"].as_u16().unwrap_or(0),
+                science_pack_drain_rate_percent: value["science_pack_drain_rate_percent"]
+                    .as_u8()
+                    .unwrap_or(100),
+                surface_conditions: get_surface_conditions(value)?,
+            })
+        }));
+
+    for prototype in ["assembling-machine", "furnace"] {
+        registry.crafting_machines.extend(load_prototypes(
+            &parsed,
+            prototype,
+            &mut warnings,
+            |value| {
+                Ok(CraftingMachinePrototype {
+                    name: get_string(value, "name")?,
+                    energy_usage: get_power(value, "energy_usage")?,
+                    crafting_speed: get_f64(value, "crafting_speed")?,
+                    crafting_categories: get_strings(value, "crafting_categories")?,
+                    energy_source: get_energy_source(&value["energy_source"])
+                        .map_err(|e| e.within("energy_source"))?,
+                    emissions_per_minute: get_emissions(&value["energy_source"])
+                        .map_err(|e| e.within("energy_source"))?,
+                    effect_receiver: get_effect_receiver(&value["effect_receiver"]),
+                    allowed_effects: get_strings(value, "allowed_effects")?,
+                    allowed_module_categories: get_strings(value, "allowed_module_categories")?,
+                    module_slots: value["module_slots"].as_u16().unwrap_or(0),
+                    surface_conditions: get_surface_conditions(value)?,
+                })
             },
-        );
+        ));
     }
 
-    for (name, value) in parsed["recipe"].entries() {
-        registry.recipes.insert(
-            name.into(),
-            RecipePrototype {
-                name: value["name"].as_str().unwrap().into(),
-                category: value["category"]
-                    .as_str()
-                    .or(Some("crafting"))
-                    .unwrap()
-                    .into(),
-                ingredients: get_materials(&value["ingredients"]),
-                results: get_materials(&value["results"]),
-                energy_required: value["energy_required"].as_f64().or(Some(0.5)).unwrap(),
+    registry
+        .recipes
+        .extend(load_prototypes(&parsed, "recipe", &mut warnings, |value| {
+            Ok(RecipePrototype {
+                name: get_string(value, "name")?,
+                category: value["category"].as_str().unwrap_or("crafting").into(),
+                ingredients: get_materials(&value["ingredients"])
+                    .map_err(|e| e.within("ingredients"))?,
+                results: get_materials(&value["results"]).map_err(|e| e.within("results"))?,
+                energy_required: value["energy_required"].as_f64().unwrap_or(0.5),
+                emissions_multiplier: value["emissions_multiplier"].as_f64().unwrap_or(1.0),
+                maximum_productivity: value["maximum_productivity"].as_f64().unwrap_or(3.0),
+                surface_conditions: get_surface_conditions(value)?,
+                enabled: value["enabled"].as_bool().unwrap_or(true),
                 allowed_effects: {
                     let mut effects: Vec<String> = vec![];
-                    if value["allow_consumption"]
-                        .as_bool()
-                        .or(Some(false))
-                        .unwrap()
-                    {
+                    if value["allow_consumption"].as_bool().unwrap_or(true) {
                         effects.push("consumption".into());
                     }
-                    if value["allow_speed"].as_bool().or(Some(false)).unwrap() {
+                    if value["allow_speed"].as_bool().unwrap_or(true) {
                         effects.push("speed".into());
                     }
-                    if value["allow_productivity"]
-                        .as_bool()
-                        .or(Some(false))
-                        .unwrap()
-                    {
+                    if value["allow_productivity"].as_bool().unwrap_or(false) {
                         effects.push("productivity".into());
                     }
-                    if value["allow_quality"].as_bool().or(Some(false)).unwrap() {
+                    if value["allow_quality"].as_bool().unwrap_or(true) {
                         effects.push("quality".into());
                     }
+                    if value["allow_pollution"].as_bool().unwrap_or(true) {
+                        effects.push("pollution".into());
+                    }
                     effects
                 },
-            },
-        );
-
-        // let process = Process::Recipe {
-        //     name: name.into(),
-        //     productivity: 0.0,
-        // };
-        // registry.processes.push(process);
-    }
+            })
+        }));
 
-    for (name, value) in parsed["module"].entries() {
-        registry.modules.insert(
-            name.into(),
-            ModulePrototype {
-                name: value["name"].as_str().unwrap().into(),
-                category: value["category"].as_str().unwrap().into(),
+    registry
+        .modules
+        .extend(load_prototypes(&parsed, "module", &mut warnings, |value| {
+            Ok(ModulePrototype {
+                name: get_string(value, "name")?,
+                category: get_string(value, "category")?,
                 effects: get_effects(&value["effect"]),
-            },
-        );
-    }
+            })
+        }));
 
-    for (name, value) in parsed["beacon"].entries() {
-        registry.beacons.insert(
-            name.into(),
-            BeaconPrototype {
-                name: value["name"].as_str().unwrap().into(),
-                energy_source: get_energy_source(&value["energy_source"]),
-                energy_usage: get_energy(value["energy_usage"].as_str().unwrap().into()),
-                efficiency: value["distribution_effectivity"].as_f64().unwrap(),
+    registry
+        .beacons
+        .extend(load_prototypes(&parsed, "beacon", &mut warnings, |value| {
+            Ok(BeaconPrototype {
+                name: get_string(value, "name")?,
+                energy_source: get_energy_source(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                energy_usage: get_power(value, "energy_usage")?,
+                efficiency: get_f64(value, "distribution_effectivity")?,
                 efficiency_per_quality: value["distribution_effectivity_bonus_per_quality_level"]
                     .as_f64()
                     .unwrap_or(0.0),
-                module_slots: value["module_slots"].as_u16().unwrap(),
-                allowed_effects: value["allowed_effects"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
-                allowed_module_categories: value["allowed_module_categories"]
-                    .members()
-                    .map(|string| string.as_str().unwrap().into())
-                    .collect(),
+                module_slots: get_u16(value, "module_slots")?,
+                allowed_effects: get_strings(value, "allowed_effects")?,
+                allowed_module_categories: get_strings(value, "allowed_module_categories")?,
                 profile: if value["profile"].is_array() {
                     Some(
                         value["profile"]
                             .members()
-                            .map(|value| value.as_f64().unwrap())
-                            .collect(),
+                            .enumerate()
+                            .map(|(index, value)| {
+                                value.as_f64().ok_or_else(|| {
+                                    FieldError::new(
+                                        &format!("profile[{}]", index),
+                                        "expected a number",
+                                    )
+                                })
+                            })
+                            .collect::<Result<_, _>>()?,
                     )
                 } else {
                     None
                 },
                 beacon_counter: value["beacon_counter"].as_str().map(|string| string.into()),
+            })
+        }));
+
+    // steam engines and turbines
+    registry.generators.extend(load_prototypes(
+        &parsed,
+        "generator",
+        &mut warnings,
+        |value| {
+            let fluid_name = value["fluid_box"]["filter"]
+                .as_str()
+                .ok_or_else(|| FieldError::new("fluid_box.filter", "expected a string"))?;
+            let fluid = registry.fluids.get(fluid_name).ok_or_else(|| {
+                FieldError::new(
+                    "fluid_box.filter",
+                    format!("unknown or skipped fluid '{}'", fluid_name),
+                )
+            })?;
+            let fluid_usage = get_f64(value, "fluid_usage_per_tick")? * 60.0;
+            let mut power = fluid_usage
+                * (get_f64(value, "maximum_temperature")? - fluid.default_temperature)
+                * fluid.heat_capacity.0
+                * value["effectivity"].as_f64().unwrap_or(1.0);
+            if !value["max_power_output"].is_null() {
+                power = power.min(get_power(value, "max_power_output")?.0);
+            }
+            Ok(GeneratorPrototype {
+                name: get_string(value, "name")?,
+                power: Energy(power),
+                energy_usage: Energy(0.0),
+                energy_source: EnergySource::Void,
+                emissions_per_minute: HashMap::new(),
+                ingredients: vec![fluid_per_second(fluid_name, fluid_usage)],
+                results: vec![],
+                solar: false,
+            })
+        },
+    ));
+
+    // boilers and heat exchangers heat their input fluid into their output fluid
+    registry
+        .generators
+        .extend(load_prototypes(&parsed, "boiler", &mut warnings, |value| {
+            let get_fluid = |key: &str| {
+                let path = format!("{}.filter", key);
+                let name = value[key]["filter"]
+                    .as_str()
+                    .ok_or_else(|| FieldError::new(&path, "expected a string"))?;
+                registry.fluids.get(name).ok_or_else(|| {
+                    FieldError::new(&path, format!("unknown or skipped fluid '{}'", name))
+                })
+            };
+            let input = get_fluid("fluid_box")?;
+            let output = get_fluid("output_fluid_box")?;
+            let energy_usage = get_power(value, "energy_consumption")?;
+            let fluid_usage = energy_usage.0
+                / ((get_f64(value, "target_temperature")? - output.default_temperature)
+                    * output.heat_capacity.0);
+            Ok(GeneratorPrototype {
+                name: get_string(value, "name")?,
+                power: Energy(0.0),
+                energy_usage,
+                energy_source: get_energy_source(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                emissions_per_minute: get_emissions(&value["energy_source"])
+                    .map_err(|e| e.within("energy_source"))?,
+                ingredients: vec![fluid_per_second(&input.name, fluid_usage)],
+                results: vec![fluid_per_second(&output.name, fluid_usage)],
+                solar: false,
+            })
+        }));
+
+    registry.generators.extend(load_prototypes(
+        &parsed,
+        "solar-panel",
+        &mut warnings,
+        |value| {
+            Ok(GeneratorPrototype {
+                name: get_string(value, "name")?,
+                power: Energy(get_power(value, "production")?.0 * SOLAR_AVERAGE),
+                energy_usage: Energy(0.0),
+                energy_source: EnergySource::Void,
+                emissions_per_minute: HashMap::new(),
+                ingredients: vec![],
+                results: vec![],
+                solar: true,
+            })
+        },
+    ));
+
+    // the fusion generator turns plasma back into the coolant of the reactor
+    registry.generators.extend(load_prototypes(
+        &parsed,
+        "fusion-generator",
+        &mut warnings,
+        |value| {
+            let fluid_usage = get_f64(value, "max_fluid_usage")? * 60.0;
+            let filter = |key: &str| {
+                value[key]["filter"]
+                    .as_str()
+                    .map(|fluid| fluid_per_second(fluid, fluid_usage))
+                    .ok_or_else(|| FieldError::new(&format!("{}.filter", key), "expected a string"))
+            };
+            Ok(GeneratorPrototype {
+                name: get_string(value, "name")?,
+                power: get_power(&value["energy_source"], "output_flow_limit")
+                    .map_err(|e| e.within("energy_source"))?,
+                energy_usage: Energy(0.0),
+                energy_source: EnergySource::Void,
+                emissions_per_minute: HashMap::new(),
+                ingredients: vec![filter("input_fluid_box")?],
+                results: vec![filter("output_fluid_box")?],
+                solar: false,
+            })
+        },
+    ));
+
+    registry.surface_properties.extend(load_prototypes(
+        &parsed,
+        "surface-property",
+        &mut warnings,
+        |value| get_f64(value, "default_value"),
+    ));
+
+    for prototype in ["planet", "surface"] {
+        registry.surfaces.extend(load_prototypes(
+            &parsed,
+            prototype,
+            &mut warnings,
+            |value| {
+                Ok(SurfacePrototype {
+                    name: get_string(value, "name")?,
+                    surface_properties: value["surface_properties"]
+                        .entries()
+                        .map(|(property, amount)| {
+                            amount
+                                .as_f64()
+                                .map(|amount| (property.to_string(), amount))
+                                .ok_or_else(|| {
+                                    FieldError::new(
+                                        &format!("surface_properties.{}", property),
+                                        "expected a number",
+                                    )
+                                })
+                        })
+                        .collect::<Result<_, _>>()?,
+                    effects: get_effects(&value["global_effect"]),
+                })
             },
-        );
+        ));
     }
 
-    Ok(registry)
+    registry.technologies.extend(load_prototypes(
+        &parsed,
+        "technology",
+        &mut warnings,
+        |value| {
+            let name = get_string(value, "name")?;
+            let level = name_level(&name);
+            Ok(TechnologyPrototype {
+                name,
+                prerequisites: get_strings(value, "prerequisites")?,
+                effects: value["effects"]
+                    .members()
+                    .enumerate()
+                    .filter_map(|(index, effect)| {
+                        get_technology_effect(effect)
+                            .map_err(|e| e.within(&format!("effects[{}]", index)))
+                            .transpose()
+                    })
+                    .collect::<Result<_, _>>()?,
+                level,
+                max_level: match value["max_level"].as_str() {
+                    Some("infinite") => None,
+                    _ if value["max_level"].is_null() => Some(level),
+                    _ => Some(get_u32(value, "max_level")?),
+                },
+                unit: if value["unit"].is_null() {
+                    None
+                } else {
+                    Some(get_technology_unit(&value["unit"]).map_err(|e| e.within("unit"))?)
+                },
+            })
+        },
+    ));
+
+    registry.qualities.extend(load_prototypes(
+        &parsed,
+        "quality",
+        &mut warnings,
+        |value| {
+            Ok(QualityPrototype {
+                name: get_string(value, "name")?,
+                level: value["level"].as_u32().unwrap_or(0),
+                next: value["next"].as_str().map(|string| string.into()),
+                next_probability: value["next_probability"].as_f64().unwrap_or(0.0),
+            })
+        },
+    ));
+    registry.qualities.retain(|name, _| {
+        !parsed["quality"][name.as_str()]["hidden"]
+            .as_bool()
+            .unwrap_or(false)
+    });
+
+    Ok((registry, warnings))
 }
 
-fn get_minable(value: &JsonValue) -> Minable {
-    Minable {
-        mining_time: value["mining_time"].as_f64().unwrap(),
-        results: value["result"].as_str().map_or_else(
-            || get_materials(&value["results"]),
-            |result| {
-                vec![Material::Item(Item {
-                    name: result.into(),
-                    quality: Some(0),
-                    amount: value["count"].as_u16().or(1.into()),
-                    amount_min: None,
-                    amount_max: None,
-                    probability: None,
-                    ignored_by_productivity: None,
-                    extra_count_fraction: None,
-                })]
-            },
-        ),
+/// The average share of its peak power a solar panel produces over a day on Nauvis.
+const SOLAR_AVERAGE: f64 = 0.7;
+
+fn fluid_per_second(name: &str, amount: f64) -> Material {
+    Material::Fluid(Fluid {
+        name: name.into(),
+        temperature: None,
+        amount: Some(amount),
+        amount_min: None,
+        amount_max: None,
+        probability: None,
+        ignored_by_productivity: None,
+    })
+}
+
+/// Loads every prototype of a category, skipping the ones that fail and recording why.
+fn load_prototypes<T>(
+    parsed: &JsonValue,
+    category: &str,
+    warnings: &mut Vec<LoadError>,
+    load: impl Fn(&JsonValue) -> Result<T, FieldError>,
+) -> Vec<(String, T)> {
+    parsed[category]
+        .entries()
+        .filter_map(|(name, value)| match load(value) {
+            Ok(prototype) => Some((name.to_string(), prototype)),
+            Err(error) => {
+                warnings.push(error.into_load_error(category, name));
+                None
+            }
+        })
+        .collect()
+}
+
+fn get_string(value: &JsonValue, key: &str) -> Result<String, FieldError> {
+    value[key]
+        .as_str()
+        .map(|string| string.into())
+        .ok_or_else(|| FieldError::new(key, "expected a string"))
+}
+
+fn get_f64(value: &JsonValue, key: &str) -> Result<f64, FieldError> {
+    value[key]
+        .as_f64()
+        .ok_or_else(|| FieldError::new(key, "expected a number"))
+}
+
+fn get_u32(value: &JsonValue, key: &str) -> Result<u32, FieldError> {
+    value[key]
+        .as_u32()
+        .ok_or_else(|| FieldError::new(key, "expected a positive integer"))
+}
+
+fn get_u16(value: &JsonValue, key: &str) -> Result<u16, FieldError> {
+    value[key]
+        .as_u16()
+        .ok_or_else(|| FieldError::new(key, "expected a positive integer"))
+}
+
+/// Reads an optional array of strings. A missing field is an empty list.
+fn get_strings(value: &JsonValue, key: &str) -> Result<Vec<String>, FieldError> {
+    value[key]
+        .members()
+        .enumerate()
+        .map(|(index, string)| {
+            string
+                .as_str()
+                .map(|string| string.into())
+                .ok_or_else(|| FieldError::new(&format!("{}[{}]", key, index), "expected a string"))
+        })
+        .collect()
+}
+
+fn get_minable(value: &JsonValue) -> Result<Minable, FieldError> {
+    Ok(Minable {
+        mining_time: get_f64(value, "mining_time")?,
+        results: match value["result"].as_str() {
+            Some(result) => vec![Material::Item(Item {
+                name: result.into(),
+                quality: None,
+                amount: value["count"].as_u16().or(1.into()),
+                amount_min: None,
+                amount_max: None,
+                probability: None,
+                ignored_by_productivity: None,
+                extra_count_fraction: None,
+            })],
+            None => get_materials(&value["results"]).map_err(|e| e.within("results"))?,
+        },
         input_fluid: if !value["required_fluid"].is_empty() {
             Some(Fluid {
-                name: value["required_fluid"].as_str().unwrap().into(),
+                name: get_string(value, "required_fluid")?,
                 temperature: None,
                 amount: value["fluid_amount"].as_f64(),
                 amount_min: None,
@@ -357,51 +721,132 @@ fn get_minable(value: &JsonValue) -> Minable {
         } else {
             None
         },
-    }
+    })
 }
-fn get_energy_source(value: &JsonValue) -> EnergySource {
-    match value["type"].as_str().unwrap() {
+fn get_energy_source(value: &JsonValue) -> Result<EnergySource, FieldError> {
+    Ok(match get_string(value, "type")?.as_str() {
         "electric" => EnergySource::Electric {
-            drain: get_energy(value["drain"].as_str().or(Some("0J")).unwrap().into()),
+            drain: if value["drain"].is_null() {
+                None
+            } else {
+                Some(get_power(value, "drain")?)
+            },
         },
         "burner" => EnergySource::Burner {
-            effectivity: value["effectivity"].as_f64().unwrap(),
-            fuel_categories: value["fuel_categories"]
-                .members()
-                .map(|string| string.as_str().unwrap().into())
-                .collect(),
+            effectivity: value["effectivity"].as_f64().unwrap_or(1.0),
+            fuel_categories: get_strings(value, "fuel_categories")?,
         },
         "heat" => EnergySource::Heat,
         "fluid" => EnergySource::Fluid {
             effectivity: value["effectivity"].as_f64().unwrap_or(1.0),
-            burns_fluid: value["burns_fluid"].as_bool().unwrap(),
+            burns_fluid: value["burns_fluid"].as_bool().unwrap_or(false),
             fluid_usage_per_tick: value["fluid_usage_per_tick"].as_u32().unwrap_or(0),
-            scale_fluid_usage: value["scale_fluid_usage"].as_bool().unwrap(),
+            scale_fluid_usage: value["scale_fluid_usage"].as_bool().unwrap_or(false),
         },
         "void" => EnergySource::Void,
-        &_ => {
-            panic!("Unknown type: {}", value["type"].as_str().unwrap());
+        other => {
+            return Err(FieldError::new(
+                "type",
+                format!("unknown energy source type '{}'", other),
+            ));
         }
+    })
+}
+/// Reads an energy in J.
+fn get_energy(value: &JsonValue, key: &str) -> Result<Energy, FieldError> {
+    Energy::parse_joules(&get_string(value, key)?).map_err(|reason| FieldError::new(key, reason))
+}
+/// Reads a power in W.
+fn get_power(value: &JsonValue, key: &str) -> Result<Energy, FieldError> {
+    Energy::parse_watts(&get_string(value, key)?).map_err(|reason| FieldError::new(key, reason))
+}
+/// Reads an optional energy in J.
+fn get_fuel_value(value: &JsonValue) -> Result<Option<Energy>, FieldError> {
+    if value["fuel_value"].is_null() {
+        Ok(None)
+    } else {
+        get_energy(value, "fuel_value").map(Some)
     }
 }
-fn get_energy(value: String) -> u32 {
-    let mut multiplier: u32 = 1;
-    match &value[value.len() - 1..] {
-        "J" => {
-            multiplier *= 60;
+/// Reads the optional surface conditions of a recipe or an entity.
+fn get_surface_conditions(value: &JsonValue) -> Result<Vec<SurfaceCondition>, FieldError> {
+    value["surface_conditions"]
+        .members()
+        .enumerate()
+        .map(|(index, condition)| {
+            Ok(SurfaceCondition {
+                property: get_string(condition, "property")
+                    .map_err(|e| e.within(&format!("surface_conditions[{}]", index)))?,
+                min: condition["min"].as_f64(),
+                max: condition["max"].as_f64(),
+            })
+        })
+        .collect()
+}
+/// Reads a technology effect the model uses, or `None` for the others.
+fn get_technology_effect(value: &JsonValue) -> Result<Option<TechnologyEffect>, FieldError> {
+    Ok(Some(match get_string(value, "type")?.as_str() {
+        "unlock-recipe" => TechnologyEffect::UnlockRecipe(get_string(value, "recipe")?),
+        "change-recipe-productivity" => TechnologyEffect::ChangeRecipeProductivity {
+            recipe: get_string(value, "recipe")?,
+            change: get_f64(value, "change")?,
+        },
+        "mining-drill-productivity-bonus" => {
+            TechnologyEffect::MiningDrillProductivityBonus(get_f64(value, "modifier")?)
         }
-        "W" => {}
-        _ => panic!(),
-    }
-
-    match &value[value.len() - 2..value.len() - 1] {
-        "k" => multiplier *= 10 ^ 3,
-        "M" => multiplier *= 10 ^ 6,
-        "G" => multiplier *= 10 ^ 9,
-        "T" => multiplier *= 10 ^ 12,
-        _ => return multiplier * value[..value.len() - 1].parse::<u32>().unwrap(),
-    }
-    (multiplier as f64 * value[..value.len() - 2].parse::<f64>().unwrap()) as u32
+        _ => return Ok(None),
+    }))
+}
+/// Returns the level of a technology named like `mining-productivity-3`, or 1 without a
+/// number.
+fn name_level(name: &str) -> u32 {
+    name.rsplit_once('-')
+        .and_then(|(_, level)| level.parse().ok())
+        .unwrap_or(1)
+}
+/// Reads the cost of a technology. Ingredients are `[name, amount]` pairs, or objects in
+/// older dumps.
+fn get_technology_unit(value: &JsonValue) -> Result<TechnologyUnit, FieldError> {
+    Ok(TechnologyUnit {
+        count: value["count"].as_f64(),
+        count_formula: value["count_formula"].as_str().map(|string| string.into()),
+        time: get_f64(value, "time")?,
+        ingredients: value["ingredients"]
+            .members()
+            .enumerate()
+            .map(|(index, ingredient)| {
+                let (name, amount) = if ingredient.is_array() {
+                    (&ingredient[0], &ingredient[1])
+                } else {
+                    (&ingredient["name"], &ingredient["amount"])
+                };
+                match (name.as_str(), amount.as_f64()) {
+                    (Some(name), Some(amount)) => Ok((name.to_string(), amount)),
+                    _ => Err(FieldError::new(
+                        &format!("ingredients[{}]", index),
+                        "expected a name and an amount",
+                    )),
+                }
+            })
+            .collect::<Result<_, _>>()?,
+    })
+}
+/// Reads the emissions of an energy source, a table of amounts per pollutant.
+fn get_emissions(value: &JsonValue) -> Result<HashMap<String, f64>, FieldError> {
+    value["emissions_per_minute"]
+        .entries()
+        .map(|(pollutant, amount)| {
+            amount
+                .as_f64()
+                .map(|amount| (pollutant.to_string(), amount))
+                .ok_or_else(|| {
+                    FieldError::new(
+                        &format!("emissions_per_minute.{}", pollutant),
+                        "expected a number",
+                    )
+                })
+        })
+        .collect()
 }
 fn get_effect_receiver(value: &JsonValue) -> Option<EffectReceiver> {
     if value.is_null() {
@@ -413,18 +858,9 @@ fn get_effect_receiver(value: &JsonValue) -> Option<EffectReceiver> {
         } else {
             None
         },
-        uses_module_effects: value["uses_module_effects"]
-            .as_bool()
-            .or(Some(false))
-            .unwrap(),
-        uses_beacon_effects: value["uses_beacon_effects"]
-            .as_bool()
-            .or(Some(false))
-            .unwrap(),
-        uses_surface_effects: value["uses_surface_effects"]
-            .as_bool()
-            .or(Some(false))
-            .unwrap(),
+        uses_module_effects: value["uses_module_effects"].as_bool().unwrap_or(false),
+        uses_beacon_effects: value["uses_beacon_effects"].as_bool().unwrap_or(false),
+        uses_surface_effects: value["uses_surface_effects"].as_bool().unwrap_or(false),
     })
 }
 
@@ -434,56 +870,132 @@ fn get_effects(value: &JsonValue) -> Effects {
         speed: value["speed"].as_f32(),
         productivity: value["productivity"].as_f32(),
         quality: value["quality"].as_f32(),
+        pollution: value["pollution"].as_f32(),
     }
 }
-fn get_materials(value: &JsonValue) -> Vec<Material> {
+fn get_materials(value: &JsonValue) -> Result<Vec<Material>, FieldError> {
     value
         .members()
-        .map(|result| match result["type"].as_str().unwrap() {
-            "item" => {
-                let mut item = Item {
-                    name: result["name"].as_str().unwrap().into(),
-                    quality: Some(0),
-                    amount: result["amount"].as_u16(),
-                    amount_min: result["amount_min"].as_u16(),
-                    amount_max: result["amount_max"].as_u16(),
-                    probability: result["probability"].as_f64(),
-                    ignored_by_productivity: result["ignored_by_productivity"].as_u16(),
-                    extra_count_fraction: result["extra_count_fraction"].as_f32(),
-                };
-                if item.amount.is_none() && item.amount_min.is_none() {
-                    item.amount = 1.into();
+        .enumerate()
+        .map(|(index, result)| {
+            let material: Result<Material, FieldError> = try {
+                match get_string(result, "type")?.as_str() {
+                    "item" => {
+                        let mut item = Item {
+                            name: get_string(result, "name")?,
+                            quality: None,
+                            amount: result["amount"].as_u16(),
+                            amount_min: result["amount_min"].as_u16(),
+                            amount_max: result["amount_max"].as_u16(),
+                            probability: result["probability"].as_f64(),
+                            ignored_by_productivity: result["ignored_by_productivity"].as_u16(),
+                            extra_count_fraction: result["extra_count_fraction"].as_f32(),
+                        };
+                        if item.amount.is_none() && item.amount_min.is_none() {
+                            item.amount = 1.into();
+                        }
+                        if item.amount.is_none() && item.amount_max.is_none() {
+                            Err(FieldError::new("amount_max", "expected a positive integer"))?;
+                        }
+                        Material::Item(item)
+                    }
+                    "fluid" => {
+                        let fluid = Fluid {
+                            name: get_string(result, "name")?,
+                            temperature: result["extra_count_fraction"].as_f32(),
+                            amount: result["amount"].as_f64(),
+                            amount_min: result["amount_min"].as_f64(),
+                            amount_max: result["amount_max"].as_f64(),
+                            probability: result["probability"].as_f64(),
+                            ignored_by_productivity: result["ignored_by_productivity"].as_u16(),
+                        };
+                        if fluid.amount.is_none()
+                            && (fluid.amount_min.is_none() || fluid.amount_max.is_none())
+                        {
+                            Err(FieldError::new("amount", "expected a number"))?;
+                        }
+                        Material::Fluid(fluid)
+                    }
+                    other => Err(FieldError::new(
+                        "type",
+                        format!("unknown material type '{}'", other),
+                    ))?,
                 }
-                Material::Item(item)
-            }
-            "fluid" => Material::Fluid(Fluid {
-                name: result["name"].as_str().unwrap().into(),
-                temperature: result["extra_count_fraction"].as_f32(),
-                amount: result["amount"].as_f64(),
-                amount_min: result["amount_min"].as_f64(),
-                amount_max: result["amount_max"].as_f64(),
-                probability: result["probability"].as_f64(),
-                ignored_by_productivity: result["ignored_by_productivity"].as_u16(),
-            }),
-            &_ => panic!("Unknown type: {}", result["type"].as_str().unwrap()),
+            };
+            material.map_err(|e| e.within(&format!("[{}]", index)))
         })
         .collect()
 }
 #[cfg(test)]
 mod tests {
-    use crate::data::data_loader::load_data;
+    use crate::data::data_loader::{load_data, parse_data, LoadError};
 
     #[test]
     fn test() {
-        let registry =
+        let (registry, _warnings) =
             load_data("E:/Games/Factorio/script-output/data-raw-dump.json".into()).unwrap();
         println!(
             "{:#?}",
             registry
                 .crafting_machines
-                .iter()
-                .map(|(_name, prototype)| { format!("{:#?}", prototype) })
+                .values()
+                .map(|prototype| { format!("{:#?}", prototype) })
                 .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn skips_invalid_prototypes() {
+        let (registry, warnings) = parse_data(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    },
+                    "odd-recipe": {
+                        "name": "odd-recipe",
+                        "ingredients": [],
+                        "results": [{"type": "research-progress", "name": "odd", "amount": 1}]
+                    }
+                },
+                "mining-drill": {
+                    "odd-drill": {
+                        "name": "odd-drill",
+                        "energy_usage": "90kX",
+                        "mining_speed": 0.5,
+                        "energy_source": {"type": "electric"}
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(registry.recipes.contains_key("iron-gear-wheel"));
+        assert!(!registry.recipes.contains_key("odd-recipe"));
+        assert!(registry.mining_drills.is_empty());
+        assert_eq!(warnings.len(), 2);
+        for warning in warnings {
+            let LoadError::Prototype {
+                category,
+                name,
+                path,
+                ..
+            } = warning
+            else {
+                panic!("unexpected fatal error");
+            };
+            match category.as_str() {
+                "recipe" => {
+                    assert_eq!(name, "odd-recipe");
+                    assert_eq!(path, "results[0].type");
+                }
+                "mining-drill" => {
+                    assert_eq!(name, "odd-drill");
+                    assert_eq!(path, "energy_usage");
+                }
+                _ => panic!("unexpected category {}", category),
+            }
+        }
+    }
 }
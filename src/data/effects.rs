@@ -1,11 +1,57 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct Effects {
     pub consumption: Option<f32>,
     pub speed: Option<f32>,
     pub productivity: Option<f32>,
     pub quality: Option<f32>,
+    pub pollution: Option<f32>,
+}
+
+impl Effects {
+    /// Adds `other` scaled by `multiplier` to these effects.
+    pub fn add(&mut self, other: &Effects, multiplier: f32) {
+        fn add_effect(effect: &mut Option<f32>, other: Option<f32>, multiplier: f32) {
+            if let Some(other) = other {
+                *effect = Some(effect.unwrap_or(0.0) + other * multiplier);
+            }
+        }
+        add_effect(&mut self.consumption, other.consumption, multiplier);
+        add_effect(&mut self.speed, other.speed, multiplier);
+        add_effect(&mut self.productivity, other.productivity, multiplier);
+        add_effect(&mut self.quality, other.quality, multiplier);
+        add_effect(&mut self.pollution, other.pollution, multiplier);
+    }
+
+    /// Drops every effect not named in `allowed_effects`.
+    pub fn retain(&mut self, allowed_effects: &[String]) {
+        let allowed = |name: &str| allowed_effects.iter().any(|effect| effect == name);
+        if !allowed("consumption") {
+            self.consumption = None;
+        }
+        if !allowed("speed") {
+            self.speed = None;
+        }
+        if !allowed("productivity") {
+            self.productivity = None;
+        }
+        if !allowed("quality") {
+            self.quality = None;
+        }
+        if !allowed("pollution") {
+            self.pollution = None;
+        }
+    }
+
+    /// Applies the game's lower limits to the summed effects.
+    pub fn clamp(&mut self) {
+        self.consumption = self.consumption.map(|value| value.max(-0.8));
+        self.speed = self.speed.map(|value| value.max(-0.8));
+        self.productivity = self.productivity.map(|value| value.max(0.0));
+        self.quality = self.quality.map(|value| value.max(0.0));
+        self.pollution = self.pollution.map(|value| value.max(-0.8));
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
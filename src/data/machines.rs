@@ -1,11 +1,15 @@
 use crate::data::effects::{EffectReceiver, Effects};
 use crate::data::materials::Material;
+use crate::data::surfaces::SurfaceCondition;
+use crate::data::types::Energy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EnergySource {
     Electric {
-        drain: u32,
+        /// The power always used, in W. Defaults to 1/30 of the energy usage of the machine.
+        drain: Option<Energy>,
     },
     Burner {
         effectivity: f64,
@@ -24,28 +28,36 @@ pub enum EnergySource {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct MiningDrillPrototype {
     pub name: String,
-    pub energy_usage: u32,
+    /// The power used while working, in W.
+    pub energy_usage: Energy,
     pub mining_speed: f64,
     pub energy_source: EnergySource,
+    /// The emissions of each pollutant per minute while working.
+    pub emissions_per_minute: HashMap<String, f64>,
     pub resource_categories: Vec<String>,
     pub effect_receiver: Option<EffectReceiver>,
     pub allowed_effects: Vec<String>,
     pub allowed_module_categories: Vec<String>,
     pub module_slots: u16,
     pub resource_drain_rate_percent: u8,
+    pub surface_conditions: Vec<SurfaceCondition>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CraftingMachinePrototype {
     pub name: String,
-    pub energy_usage: u32,
+    /// The power used while working, in W.
+    pub energy_usage: Energy,
     pub crafting_speed: f64,
     pub crafting_categories: Vec<String>,
     pub energy_source: EnergySource,
+    /// The emissions of each pollutant per minute while working.
+    pub emissions_per_minute: HashMap<String, f64>,
     pub effect_receiver: Option<EffectReceiver>,
     pub allowed_effects: Vec<String>,
     pub allowed_module_categories: Vec<String>,
     pub module_slots: u16,
+    pub surface_conditions: Vec<SurfaceCondition>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -55,7 +67,33 @@ pub struct RecipePrototype {
     pub ingredients: Vec<Material>,
     pub results: Vec<Material>,
     pub energy_required: f64,
+    pub emissions_multiplier: f64,
+    /// The highest productivity bonus the recipe can reach.
+    pub maximum_productivity: f64,
     pub allowed_effects: Vec<String>,
+    pub surface_conditions: Vec<SurfaceCondition>,
+    /// Whether the recipe is available without research.
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabPrototype {
+    pub name: String,
+    /// The power used while working, in W.
+    pub energy_usage: Energy,
+    pub researching_speed: f64,
+    /// The science packs the lab accepts.
+    pub inputs: Vec<String>,
+    pub energy_source: EnergySource,
+    /// The emissions of each pollutant per minute while working.
+    pub emissions_per_minute: HashMap<String, f64>,
+    pub effect_receiver: Option<EffectReceiver>,
+    pub allowed_effects: Vec<String>,
+    pub allowed_module_categories: Vec<String>,
+    pub module_slots: u16,
+    /// The share of each science pack a research unit uses, in percent.
+    pub science_pack_drain_rate_percent: u8,
+    pub surface_conditions: Vec<SurfaceCondition>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -68,7 +106,8 @@ pub struct ModulePrototype {
 pub struct BeaconPrototype {
     pub name: String,
     pub energy_source: EnergySource,
-    pub energy_usage: u32,
+    /// The power used while working, in W.
+    pub energy_usage: Energy,
     pub efficiency: f64,
     pub efficiency_per_quality: f64,
     pub module_slots: u16,
@@ -77,3 +116,105 @@ pub struct BeaconPrototype {
     pub profile: Option<Vec<f64>>,
     pub beacon_counter: Option<String>,
 }
+
+/// An entity producing electricity or heating fluids. The amounts are what it uses and makes
+/// per second at full power.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorPrototype {
+    pub name: String,
+    /// The electric power produced, in W.
+    pub power: Energy,
+    /// The power used, in W, coming from `energy_source`.
+    pub energy_usage: Energy,
+    pub energy_source: EnergySource,
+    /// The emissions of each pollutant per minute while working.
+    pub emissions_per_minute: HashMap<String, f64>,
+    pub ingredients: Vec<Material>,
+    pub results: Vec<Material>,
+    /// Whether the power follows the solar power of the surface, in percent.
+    pub solar: bool,
+}
+
+/// The machine running a process: a mining drill, a crafting machine or a lab.
+#[derive(Clone, Copy)]
+#[allow(clippy::enum_variant_names)]
+pub enum Machine<'a> {
+    MiningDrill(&'a MiningDrillPrototype),
+    CraftingMachine(&'a CraftingMachinePrototype),
+    Lab(&'a LabPrototype),
+}
+
+impl<'a> Machine<'a> {
+    pub fn get_speed(&self) -> f64 {
+        match self {
+            Machine::MiningDrill(drill) => drill.mining_speed,
+            Machine::CraftingMachine(machine) => machine.crafting_speed,
+            Machine::Lab(lab) => lab.researching_speed,
+        }
+    }
+
+    pub fn get_energy_usage(&self) -> Energy {
+        match self {
+            Machine::MiningDrill(drill) => drill.energy_usage,
+            Machine::CraftingMachine(machine) => machine.energy_usage,
+            Machine::Lab(lab) => lab.energy_usage,
+        }
+    }
+
+    pub fn get_energy_source(&self) -> &'a EnergySource {
+        match self {
+            Machine::MiningDrill(drill) => &drill.energy_source,
+            Machine::CraftingMachine(machine) => &machine.energy_source,
+            Machine::Lab(lab) => &lab.energy_source,
+        }
+    }
+
+    pub fn get_emissions_per_minute(&self) -> &'a HashMap<String, f64> {
+        match self {
+            Machine::MiningDrill(drill) => &drill.emissions_per_minute,
+            Machine::CraftingMachine(machine) => &machine.emissions_per_minute,
+            Machine::Lab(lab) => &lab.emissions_per_minute,
+        }
+    }
+
+    pub fn get_surface_conditions(&self) -> &'a Vec<SurfaceCondition> {
+        match self {
+            Machine::MiningDrill(drill) => &drill.surface_conditions,
+            Machine::CraftingMachine(machine) => &machine.surface_conditions,
+            Machine::Lab(lab) => &lab.surface_conditions,
+        }
+    }
+
+    pub fn get_module_slots(&self) -> u16 {
+        match self {
+            Machine::MiningDrill(drill) => drill.module_slots,
+            Machine::CraftingMachine(machine) => machine.module_slots,
+            Machine::Lab(lab) => lab.module_slots,
+        }
+    }
+
+    pub fn get_effect_receiver(&self) -> EffectReceiver {
+        match self {
+            Machine::MiningDrill(drill) => drill.effect_receiver,
+            Machine::CraftingMachine(machine) => machine.effect_receiver,
+            Machine::Lab(lab) => lab.effect_receiver,
+        }
+        .unwrap_or_default()
+    }
+
+    pub fn get_allowed_effects(&self) -> &'a Vec<String> {
+        match self {
+            Machine::MiningDrill(drill) => &drill.allowed_effects,
+            Machine::CraftingMachine(machine) => &machine.allowed_effects,
+            Machine::Lab(lab) => &lab.allowed_effects,
+        }
+    }
+
+    pub fn get_allowed_module_categories(&self) -> &'a Vec<String> {
+        match self {
+            Machine::MiningDrill(drill) => &drill.allowed_module_categories,
+            Machine::CraftingMachine(machine) => &machine.allowed_module_categories,
+            Machine::Lab(lab) => &lab.allowed_module_categories,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::data::quality::NORMAL_QUALITY;
+use crate::data::types::Energy;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -8,8 +10,13 @@ pub enum Material {
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub enum MaterialPrototype {
-    Item(String),
+    /// An item by name and quality.
+    Item(String, String),
     Fluid(String),
+    /// Electric energy, in MJ. Flows of it are in MW.
+    Electricity,
+    /// Research units of a technology.
+    Research(String),
 }
 
 impl MaterialPrototype {
@@ -21,17 +28,30 @@ impl MaterialPrototype {
     //     .clone()
     // }
 
+    /// Returns the id of the material, e.g. `item:iron-plate` or `item:iron-plate@legendary`.
     pub fn to_id(&self) -> String {
         match self {
-            MaterialPrototype::Item(item) => format!("item:{}", item),
+            MaterialPrototype::Item(item, quality) if quality == NORMAL_QUALITY => {
+                format!("item:{}", item)
+            }
+            MaterialPrototype::Item(item, quality) => format!("item:{}@{}", item, quality),
             MaterialPrototype::Fluid(fluid) => format!("fluid:{}", fluid),
+            MaterialPrototype::Electricity => "electricity".into(),
+            MaterialPrototype::Research(technology) => format!("research:{}", technology),
         }
     }
 
     pub fn from_id(id: &str) -> Result<Self, String> {
+        if id == "electricity" {
+            return Ok(MaterialPrototype::Electricity);
+        }
         match id.split_once(':') {
-            Some(("item", item)) => Ok(MaterialPrototype::Item(item.into())),
+            Some(("item", item)) => Ok(match item.rsplit_once('@') {
+                Some((item, quality)) => MaterialPrototype::Item(item.into(), quality.into()),
+                None => MaterialPrototype::Item(item.into(), NORMAL_QUALITY.into()),
+            }),
             Some(("fluid", fluid)) => Ok(MaterialPrototype::Fluid(fluid.into())),
+            Some(("research", technology)) => Ok(MaterialPrototype::Research(technology.into())),
             _ => Err(format!("Invalid material type '{}'", id)),
         }
     }
@@ -40,7 +60,10 @@ impl MaterialPrototype {
 impl Material {
     pub fn get_prototype(&self) -> MaterialPrototype {
         match self {
-            Material::Item(item) => MaterialPrototype::Item(item.name.clone()),
+            Material::Item(item) => MaterialPrototype::Item(
+                item.name.clone(),
+                item.quality.clone().unwrap_or(NORMAL_QUALITY.into()),
+            ),
             Material::Fluid(fluid) => MaterialPrototype::Fluid(fluid.name.clone()),
         }
     }
@@ -77,7 +100,8 @@ pub struct ItemPrototype {
     pub name: String,
     pub stack_size: u32,
     pub fuel_category: Option<String>,
-    pub fuel_value: Option<u32>,
+    /// The energy released when burnt, in J.
+    pub fuel_value: Option<Energy>,
     pub burnt_result: Option<String>,
     pub spoil_result: Option<String>,
     pub plant_result: Option<String>,
@@ -86,7 +110,8 @@ pub struct ItemPrototype {
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Item {
     pub name: String,
-    pub quality: Option<u8>,
+    /// The quality of the item, or `None` when it follows the quality of the process.
+    pub quality: Option<String>,
     pub amount: Option<u16>,
     pub amount_min: Option<u16>,
     pub amount_max: Option<u16>,
@@ -98,7 +123,12 @@ pub struct Item {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct FluidPrototype {
     pub name: String,
-    pub fuel_value: Option<u32>,
+    /// The energy released when burnt, in J.
+    pub fuel_value: Option<Energy>,
+    /// The energy it takes to heat one unit by one degree, in J.
+    pub heat_capacity: Energy,
+    pub default_temperature: f64,
+    pub max_temperature: Option<f64>,
 }
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Fluid {
@@ -0,0 +1,101 @@
+use crate::data::Registry;
+use serde::{Deserialize, Serialize};
+
+/// The quality every material and process has when nothing else is specified.
+pub const NORMAL_QUALITY: &str = "normal";
+
+/// Quality effects in the dump are expressed in units of 10% chance.
+const QUALITY_EFFECT_SCALE: f64 = 0.1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QualityPrototype {
+    pub name: String,
+    pub level: u32,
+    pub next: Option<String>,
+    pub next_probability: f64,
+}
+
+/// Returns every quality name ordered by level, or only the normal quality for dumps
+/// without quality prototypes.
+pub fn get_quality_names(registry: &Registry) -> Vec<String> {
+    if registry.qualities.is_empty() {
+        return vec![NORMAL_QUALITY.into()];
+    }
+    let mut qualities: Vec<&QualityPrototype> = registry.qualities.values().collect();
+    qualities.sort_by_key(|quality| quality.level);
+    qualities
+        .into_iter()
+        .map(|quality| quality.name.clone())
+        .collect()
+}
+
+/// Returns the share of crafts whose results land in each quality, for a craft made from
+/// `quality` ingredients with the given quality effect.
+///
+/// With the quality chance the result moves up one tier, and from there keeps moving up
+/// with the `next_probability` of the tier it reached.
+pub fn get_quality_distribution(
+    registry: &Registry,
+    quality: &str,
+    quality_effect: f32,
+) -> Vec<(String, f64)> {
+    let mut distribution: Vec<(String, f64)> = vec![];
+    let mut current = quality.to_string();
+    let mut share = 1.0;
+    let mut chance = (quality_effect as f64 * QUALITY_EFFECT_SCALE).clamp(0.0, 1.0);
+    while let Some(next) = registry
+        .qualities
+        .get(&current)
+        .and_then(|prototype| prototype.next.clone())
+        .filter(|next| registry.qualities.contains_key(next) && chance > 0.0)
+    {
+        distribution.push((current, share * (1.0 - chance)));
+        share *= chance;
+        chance = registry.qualities.get(&next).unwrap().next_probability;
+        current = next;
+    }
+    distribution.push((current, share));
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::data_loader::parse_data;
+    use crate::data::quality::{get_quality_distribution, get_quality_names};
+
+    #[test]
+    fn quality_distribution() {
+        let (registry, _warnings) = parse_data(
+            r#"{
+                "quality": {
+                    "normal": {"name": "normal", "level": 0, "next": "uncommon", "next_probability": 0.1},
+                    "uncommon": {"name": "uncommon", "level": 1, "next": "rare", "next_probability": 0.1},
+                    "rare": {"name": "rare", "level": 2},
+                    "quality-unknown": {"name": "quality-unknown", "level": 0, "hidden": true}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(get_quality_names(&registry), ["normal", "uncommon", "rare"]);
+
+        // four tier 3 quality modules: 4 * 0.25 effect = 10% chance
+        let distribution = get_quality_distribution(&registry, "normal", 1.0);
+        let expected = [("normal", 0.9), ("uncommon", 0.09), ("rare", 0.01)];
+        assert_eq!(distribution.len(), expected.len());
+        for ((quality, share), (expected_quality, expected_share)) in
+            distribution.iter().zip(expected)
+        {
+            assert_eq!(quality, expected_quality);
+            assert!((share - expected_share).abs() < 1e-9);
+        }
+
+        assert_eq!(
+            get_quality_distribution(&registry, "rare", 1.0),
+            [("rare".to_string(), 1.0)]
+        );
+        assert_eq!(
+            get_quality_distribution(&registry, "normal", 0.0),
+            [("normal".to_string(), 1.0)]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::data::materials::{Fluid, Material};
+use crate::data::surfaces::SurfaceCondition;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -13,8 +14,12 @@ pub struct PlantPrototype {
     pub growth_ticks: u32,
     pub results: Minable,
     pub seeds: Vec<String>,
+    pub surface_conditions: Vec<SurfaceCondition>,
 }
 
+/// The category of the resources pumped from tiles, like water, by offshore pumps.
+pub const TILE_CATEGORY: &str = "calculator internal tile";
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ResourcePrototype {
     pub name: String,
@@ -0,0 +1,116 @@
+use crate::data::effects::Effects;
+use crate::data::Registry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A planet or another surface a factory can be built on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SurfacePrototype {
+    pub name: String,
+    /// The surface properties that differ from their default value.
+    pub surface_properties: HashMap<String, f64>,
+    /// The effects applied to every machine using surface effects.
+    pub effects: Effects,
+}
+
+/// A range a surface property must be within for a recipe or a machine to work.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SurfaceCondition {
+    pub property: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Returns the value of `property` on `surface`, or its default value.
+pub fn get_surface_property(registry: &Registry, surface: &str, property: &str) -> f64 {
+    registry
+        .surfaces
+        .get(surface)
+        .and_then(|surface| surface.surface_properties.get(property))
+        .or_else(|| registry.surface_properties.get(property))
+        .copied()
+        .unwrap_or(0.0)
+}
+
+/// Returns whether every condition holds on `surface`. Without a surface nothing is ruled out.
+pub fn check_surface_conditions(
+    registry: &Registry,
+    conditions: &[SurfaceCondition],
+    surface: Option<&str>,
+) -> bool {
+    let Some(surface) = surface else {
+        return true;
+    };
+    conditions.iter().all(|condition| {
+        let value = get_surface_property(registry, surface, &condition.property);
+        condition.min.is_none_or(|min| value >= min) && condition.max.is_none_or(|max| value <= max)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::{set_test_registry, Process, ProcessType};
+
+    #[test]
+    fn surface_conditions_and_effects() {
+        let _registry = set_test_registry(
+            r#"{
+                "surface-property": {
+                    "pressure": {"name": "pressure", "default_value": 1000},
+                    "solar-power": {"name": "solar-power", "default_value": 100}
+                },
+                "planet": {
+                    "nauvis": {"name": "nauvis"},
+                    "vulcanus": {
+                        "name": "vulcanus",
+                        "surface_properties": {"pressure": 4000, "solar-power": 400},
+                        "global_effect": {"productivity": 0.5}
+                    }
+                },
+                "recipe": {
+                    "tungsten-carbide": {
+                        "name": "tungsten-carbide",
+                        "allow_productivity": true,
+                        "ingredients": [],
+                        "results": [{"type": "item", "name": "tungsten-carbide", "amount": 1}],
+                        "surface_conditions": [{"property": "pressure", "min": 4000, "max": 4000}]
+                    }
+                },
+                "assembling-machine": {
+                    "foundry": {
+                        "name": "foundry",
+                        "energy_usage": "2.5MW",
+                        "crafting_speed": 4,
+                        "crafting_categories": ["crafting"],
+                        "energy_source": {"type": "electric"},
+                        "effect_receiver": {"uses_surface_effects": true},
+                        "allowed_effects": ["productivity"]
+                    }
+                },
+                "solar-panel": {
+                    "solar-panel": {"name": "solar-panel", "production": "60kW"}
+                }
+            }"#,
+        );
+        let process = Process {
+            machine: Some("foundry".into()),
+            ..Process::new(ProcessType::Recipe, "tungsten-carbide".into())
+        };
+        assert!(process.can_run_on(None));
+        assert!(process.can_run_on(Some("vulcanus")));
+        assert!(!process.can_run_on(Some("nauvis")));
+
+        assert_eq!(process.get_effects(None).productivity, None);
+        assert_eq!(process.get_effects(Some("nauvis")).productivity, None);
+        assert_eq!(
+            process.get_effects(Some("vulcanus")).productivity,
+            Some(0.5)
+        );
+
+        let solar_panel = Process::new(ProcessType::Generator, "solar-panel".into());
+        let nauvis = solar_panel.get_electricity(Some("nauvis"));
+        let vulcanus = solar_panel.get_electricity(Some("vulcanus"));
+        assert!((nauvis - 0.042).abs() < 1e-9);
+        assert!((vulcanus - 4.0 * nauvis).abs() < 1e-9);
+    }
+}
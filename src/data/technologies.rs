@@ -0,0 +1,351 @@
+use crate::data::resources::TILE_CATEGORY;
+use crate::data::{get_registry, Process, ProcessType, Registry};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TechnologyPrototype {
+    pub name: String,
+    pub prerequisites: Vec<String>,
+    pub effects: Vec<TechnologyEffect>,
+    /// The level of the technology, taken from the number ending its name.
+    pub level: u32,
+    /// The last level the technology can be researched to, or `None` if it's infinite.
+    pub max_level: Option<u32>,
+    /// The cost of the research, or `None` for technologies researched by a trigger.
+    pub unit: Option<TechnologyUnit>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TechnologyEffect {
+    UnlockRecipe(String),
+    ChangeRecipeProductivity { recipe: String, change: f64 },
+    MiningDrillProductivityBonus(f64),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TechnologyUnit {
+    /// The number of units, or `None` when it's given by `count_formula`.
+    pub count: Option<f64>,
+    pub count_formula: Option<String>,
+    /// The time one unit takes at lab speed 1, in seconds.
+    pub time: f64,
+    /// The science packs one unit uses.
+    pub ingredients: Vec<(String, f64)>,
+}
+
+impl TechnologyPrototype {
+    pub fn get_unlocked_recipes(&self) -> impl Iterator<Item = &String> {
+        self.effects.iter().filter_map(|effect| match effect {
+            TechnologyEffect::UnlockRecipe(recipe) => Some(recipe),
+            _ => None,
+        })
+    }
+
+    /// Returns how many levels of the technology are researched. `level` is the last
+    /// researched level of a repeatable technology, otherwise its researched state counts as
+    /// one level.
+    pub fn get_researched_levels(&self, researched: bool, level: Option<u32>) -> u32 {
+        match level {
+            Some(level) => (level + 1).saturating_sub(self.level).min(
+                self.max_level.map_or(u32::MAX, |max_level| {
+                    (max_level + 1).saturating_sub(self.level)
+                }),
+            ),
+            None => researched as u32,
+        }
+    }
+}
+
+/// The productivity research adds on top of the effects of machines.
+#[derive(Clone, Debug, Default)]
+pub struct ProductivityBonuses {
+    pub recipes: HashMap<String, f64>,
+    pub mining: f64,
+}
+
+impl ProductivityBonuses {
+    /// Sums the productivity effects of the researched technologies. `levels` holds the last
+    /// researched level of repeatable technologies.
+    pub fn new(
+        registry: &Registry,
+        researched: Option<&HashSet<String>>,
+        levels: &HashMap<String, u32>,
+    ) -> Self {
+        let mut bonuses = ProductivityBonuses::default();
+        for technology in registry.technologies.values() {
+            let count = technology.get_researched_levels(
+                researched.is_some_and(|researched| researched.contains(&technology.name)),
+                levels.get(&technology.name).copied(),
+            ) as f64;
+            if count == 0.0 {
+                continue;
+            }
+            for effect in technology.effects.iter() {
+                match effect {
+                    TechnologyEffect::UnlockRecipe(_) => {}
+                    TechnologyEffect::ChangeRecipeProductivity { recipe, change } => {
+                        *bonuses.recipes.entry(recipe.clone()).or_default() += change * count;
+                    }
+                    TechnologyEffect::MiningDrillProductivityBonus(bonus) => {
+                        bonuses.mining += bonus * count;
+                    }
+                }
+            }
+        }
+        bonuses
+    }
+
+    /// Returns the productivity research adds to `process`. Mining productivity only boosts
+    /// resources mined by mining drills, not fluids pumped from tiles.
+    pub fn get(&self, process: &Process) -> f64 {
+        match process.process_type {
+            ProcessType::Resource
+                if get_registry()
+                    .resources
+                    .get(&process.name)
+                    .is_some_and(|resource| resource.category == TILE_CATEGORY) =>
+            {
+                0.0
+            }
+            ProcessType::Resource => self.mining,
+            ProcessType::Recipe => self.recipes.get(&process.name).copied().unwrap_or(0.0),
+            ProcessType::Plant
+            | ProcessType::Generator
+            | ProcessType::Research
+            | ProcessType::Void => 0.0,
+        }
+    }
+}
+
+/// Returns the technologies unlocking `recipe`, sorted by name.
+pub fn get_unlocking_technologies(registry: &Registry, recipe: &str) -> Vec<String> {
+    let mut technologies: Vec<String> = registry
+        .technologies
+        .values()
+        .filter(|technology| technology.get_unlocked_recipes().any(|name| name == recipe))
+        .map(|technology| technology.name.clone())
+        .collect();
+    technologies.sort();
+    technologies
+}
+
+/// Returns whether `recipe` is available with the `researched` technologies. When research
+/// isn't tracked every recipe is.
+pub fn is_recipe_unlocked(
+    registry: &Registry,
+    researched: Option<&HashSet<String>>,
+    recipe: &str,
+) -> bool {
+    let Some(researched) = researched else {
+        return true;
+    };
+    registry
+        .recipes
+        .get(recipe)
+        .is_some_and(|prototype| prototype.enabled)
+        || researched.iter().any(|technology| {
+            registry
+                .technologies
+                .get(technology)
+                .is_some_and(|technology| {
+                    technology.get_unlocked_recipes().any(|name| name == recipe)
+                })
+        })
+}
+
+/// Marks `technology` and everything it requires as researched.
+pub fn research(registry: &Registry, researched: &mut HashSet<String>, technology: &str) {
+    let mut pending = vec![technology.to_string()];
+    while let Some(technology) = pending.pop() {
+        if let Some(prototype) = registry.technologies.get(&technology)
+            && researched.insert(technology)
+        {
+            pending.extend(prototype.prerequisites.iter().cloned());
+        }
+    }
+}
+
+/// Marks `technology` and everything requiring it as not researched.
+pub fn unresearch(registry: &Registry, researched: &mut HashSet<String>, technology: &str) {
+    let mut pending = vec![technology.to_string()];
+    while let Some(technology) = pending.pop() {
+        if researched.remove(&technology) {
+            pending.extend(
+                registry
+                    .technologies
+                    .values()
+                    .filter(|prototype| prototype.prerequisites.contains(&technology))
+                    .map(|prototype| prototype.name.clone()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::data_loader::parse_data;
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::technologies::{
+        get_unlocking_technologies, is_recipe_unlocked, research, unresearch, ProductivityBonuses,
+    };
+    use crate::data::{get_registry, set_test_registry, Process, ProcessType};
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn research_unlocks_recipes() {
+        let (registry, warnings) = parse_data(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {"name": "iron-gear-wheel", "ingredients": [], "results": []},
+                    "assembling-machine-1": {
+                        "name": "assembling-machine-1",
+                        "enabled": false,
+                        "ingredients": [],
+                        "results": []
+                    },
+                    "assembling-machine-2": {
+                        "name": "assembling-machine-2",
+                        "enabled": false,
+                        "ingredients": [],
+                        "results": []
+                    }
+                },
+                "technology": {
+                    "automation": {
+                        "name": "automation",
+                        "effects": [{"type": "unlock-recipe", "recipe": "assembling-machine-1"}],
+                        "unit": {"count": 10, "time": 10, "ingredients": [["automation-science-pack", 1]]}
+                    },
+                    "automation-2": {
+                        "name": "automation-2",
+                        "prerequisites": ["automation"],
+                        "effects": [
+                            {"type": "unlock-recipe", "recipe": "assembling-machine-2"},
+                            {"type": "character-logistic-requests", "modifier": true}
+                        ],
+                        "unit": {
+                            "count": 40,
+                            "time": 5,
+                            "ingredients": [
+                                ["automation-science-pack", 1],
+                                ["logistic-science-pack", 1]
+                            ]
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            registry
+                .technologies
+                .get("automation-2")
+                .unwrap()
+                .effects
+                .len(),
+            1
+        );
+        assert_eq!(
+            get_unlocking_technologies(&registry, "assembling-machine-2"),
+            ["automation-2"]
+        );
+
+        let mut researched = HashSet::new();
+        assert!(is_recipe_unlocked(&registry, None, "assembling-machine-2"));
+        assert!(is_recipe_unlocked(
+            &registry,
+            Some(&researched),
+            "iron-gear-wheel"
+        ));
+        assert!(!is_recipe_unlocked(
+            &registry,
+            Some(&researched),
+            "assembling-machine-1"
+        ));
+
+        research(&registry, &mut researched, "automation-2");
+        assert_eq!(researched.len(), 2);
+        assert!(is_recipe_unlocked(
+            &registry,
+            Some(&researched),
+            "assembling-machine-1"
+        ));
+
+        unresearch(&registry, &mut researched, "automation");
+        assert!(researched.is_empty());
+    }
+
+    #[test]
+    fn research_adds_productivity() {
+        let _registry = set_test_registry(
+            r#"{
+                "item": {
+                    "steel-plate": {"name": "steel-plate", "stack_size": 100},
+                    "iron-ore": {"name": "iron-ore", "stack_size": 50}
+                },
+                "recipe": {
+                    "steel-plate": {
+                        "name": "steel-plate",
+                        "maximum_productivity": 0.5,
+                        "ingredients": [],
+                        "results": [{"type": "item", "name": "steel-plate", "amount": 1}]
+                    }
+                },
+                "resource": {
+                    "iron-ore": {
+                        "name": "iron-ore",
+                        "category": "basic-solid",
+                        "minable": {"mining_time": 1, "result": "iron-ore"}
+                    }
+                },
+                "tile": {
+                    "water": {"name": "water", "fluid": "water"}
+                },
+                "technology": {
+                    "steel-plate-productivity": {
+                        "name": "steel-plate-productivity",
+                        "max_level": "infinite",
+                        "effects": [
+                            {"type": "change-recipe-productivity", "recipe": "steel-plate", "change": 0.1}
+                        ]
+                    },
+                    "mining-productivity-1": {
+                        "name": "mining-productivity-1",
+                        "effects": [{"type": "mining-drill-productivity-bonus", "modifier": 0.1}]
+                    },
+                    "mining-productivity-3": {
+                        "name": "mining-productivity-3",
+                        "max_level": "infinite",
+                        "effects": [{"type": "mining-drill-productivity-bonus", "modifier": 0.1}]
+                    }
+                }
+            }"#,
+        );
+        let researched = HashSet::from(["mining-productivity-1".to_string()]);
+        let levels = HashMap::from([
+            ("steel-plate-productivity".to_string(), 3),
+            ("mining-productivity-3".to_string(), 4),
+        ]);
+        let bonuses = ProductivityBonuses::new(&get_registry(), Some(&researched), &levels);
+        assert!((bonuses.mining - 0.3).abs() < 1e-9);
+        assert!((bonuses.recipes["steel-plate"] - 0.3).abs() < 1e-9);
+
+        let ore = Process::new(ProcessType::Resource, "iron-ore".into());
+        assert!((ore.get_productivity(None, &bonuses) - 0.3).abs() < 1e-9);
+        // offshore pumps aren't mining drills
+        let water = Process::new(ProcessType::Resource, "water *tile".into());
+        assert_eq!(water.get_productivity(None, &bonuses), 0.0);
+
+        let levels = HashMap::from([("steel-plate-productivity".to_string(), 8)]);
+        let bonuses = ProductivityBonuses::new(&get_registry(), None, &levels);
+        let steel = Process::new(ProcessType::Recipe, "steel-plate".into());
+        let amounts = steel.get_product_amounts(None, &bonuses);
+        assert_eq!(
+            amounts[0].0,
+            MaterialPrototype::Item("steel-plate".into(), "normal".into())
+        );
+        assert!((amounts[0].1 - 1.5).abs() < 1e-9);
+    }
+}
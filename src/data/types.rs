@@ -1 +1,110 @@
+use serde::{Deserialize, Serialize};
 
+/// An amount of energy in J, or of power in W, depending on the field holding it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Energy(pub f64);
+
+const SI_PREFIXES: [(char, f64); 10] = [
+    ('k', 1e3),
+    ('M', 1e6),
+    ('G', 1e9),
+    ('T', 1e12),
+    ('P', 1e15),
+    ('E', 1e18),
+    ('Z', 1e21),
+    ('Y', 1e24),
+    ('R', 1e27),
+    ('Q', 1e30),
+];
+
+impl Energy {
+    /// Parses an energy string like `4MJ` into J. The game reads W as J per second and
+    /// stores energy per tick, so `60W` is `1J`.
+    pub fn parse_joules(string: &str) -> Result<Self, String> {
+        let (value, unit) = parse(string)?;
+        Ok(Energy(match unit {
+            'J' => value,
+            _ => value / 60.0,
+        }))
+    }
+
+    /// Parses a power string like `75kW` into W. A J value is energy per tick, so `1J` is
+    /// `60W`.
+    pub fn parse_watts(string: &str) -> Result<Self, String> {
+        let (value, unit) = parse(string)?;
+        Ok(Energy(match unit {
+            'J' => value * 60.0,
+            _ => value,
+        }))
+    }
+
+    pub fn to_mega(self) -> f64 {
+        self.0 / 1e6
+    }
+}
+
+/// Splits an energy string into its value, with the SI prefix applied, and its unit.
+fn parse(string: &str) -> Result<(f64, char), String> {
+    let invalid = || format!("invalid energy value '{}'", string);
+    let mut chars = string.chars();
+    let unit = chars.next_back().filter(|unit| matches!(unit, 'J' | 'W'));
+    let Some(unit) = unit else {
+        return Err(invalid());
+    };
+    let mut number = chars.as_str();
+    let mut multiplier = 1.0;
+    if let Some(&(prefix, prefix_multiplier)) = SI_PREFIXES
+        .iter()
+        .find(|(prefix, _)| number.ends_with(*prefix))
+    {
+        number = &number[..number.len() - prefix.len_utf8()];
+        multiplier = prefix_multiplier;
+    }
+    let value = number.parse::<f64>().map_err(|_| invalid())?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(invalid());
+    }
+    Ok((value * multiplier, unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::types::Energy;
+
+    #[test]
+    fn parse_energy() {
+        let cases = [
+            ("5J", 5.0),
+            ("0.2kJ", 200.0),
+            ("4MJ", 4e6),
+            ("1.21GJ", 1.21e9),
+            ("2TJ", 2e12),
+            ("3PJ", 3e15),
+            ("1EJ", 1e18),
+            ("1ZJ", 1e21),
+            ("1YJ", 1e24),
+            ("1RJ", 1e27),
+            ("1QJ", 1e30),
+        ];
+        for (string, joules) in cases {
+            let energy = Energy::parse_joules(string).unwrap();
+            assert!((energy.0 - joules).abs() <= joules * 1e-12, "{}", string);
+            let power = Energy::parse_watts(string).unwrap();
+            assert!(
+                (power.0 - joules * 60.0).abs() <= joules * 1e-10,
+                "{}",
+                string
+            );
+        }
+
+        assert_eq!(Energy::parse_watts("75kW"), Ok(Energy(75e3)));
+        assert_eq!(Energy::parse_watts("5.82MW"), Ok(Energy(5.82e6)));
+        assert_eq!(Energy::parse_watts("2.5GW"), Ok(Energy(2.5e9)));
+        assert_eq!(Energy::parse_joules("60W"), Ok(Energy(1.0)));
+        assert_eq!(Energy::parse_watts("0W"), Ok(Energy(0.0)));
+
+        for string in ["", "W", "kW", "90kX", "5", "1.5mW", "-5kW", "NaNW", "abcJ"] {
+            assert!(Energy::parse_joules(string).is_err(), "{}", string);
+        }
+    }
+}
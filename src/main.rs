@@ -1,7 +1,4 @@
-#![feature(fn_traits)]
 #![feature(unboxed_closures)]
-#![feature(tuple_trait)]
-#![feature(if_let_guard)]
 #![feature(try_blocks)]
 
 mod data;
@@ -15,5 +12,5 @@ fn main() -> Result<(), Box<dyn Error>> {
     unsafe {
         env::set_var("RUST_BACKTRACE", "full");
     }
-    Ok(ui::main()?)
+    ui::main()
 }
@@ -1,29 +1,133 @@
 use crate::data::materials::MaterialPrototype;
-use crate::data::Process;
+use crate::data::technologies::ProductivityBonuses;
+use crate::data::{get_registry, Process, ProcessType};
+use crate::model::balance::MaterialBalance;
+use crate::model::diagnosis::Infeasibility;
+use crate::model::limit::Limit;
+use crate::model::objective::Objective;
+use crate::model::quality_loop::QualityLoop;
+use crate::model::ratio::Ratio;
+use crate::model::recipe_rules::RecipeRule;
+use crate::model::solution::Solution;
+use crate::model::time_unit::TimeUnit;
 use good_lp::SolutionStatus::Optimal;
 use good_lp::{
     microlp, variable, Constraint, Expression, IntoAffineExpression, ProblemVariables,
-    ResolutionError, Solution, SolverModel, Variable,
+    ResolutionError, Solution as _, SolverModel, Variable,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+pub mod auto_build;
+pub mod balance;
+pub mod diagnosis;
+pub mod limit;
+pub mod objective;
+pub mod quality_loop;
+pub mod ratio;
+pub mod recipe_rules;
+pub mod research;
+pub mod sensitivity;
+pub mod solution;
+pub mod time_unit;
+pub mod whole_machines;
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Model {
     pub processes: Vec<Process>,
+    /// The input limits, per second.
     pub inputs: HashMap<MaterialPrototype, f64>,
+    /// The output targets, per second.
     pub outputs: HashMap<MaterialPrototype, f64>,
+    #[serde(default)]
+    pub quality_loops: Vec<QualityLoop>,
+    /// The surface the factory is built on.
+    #[serde(default)]
+    pub surface: Option<String>,
+    /// The researched technologies, or `None` when research isn't tracked and every recipe is
+    /// available.
+    #[serde(default)]
+    pub researched: Option<HashSet<String>>,
+    /// The last researched level of each repeatable technology.
+    #[serde(default)]
+    pub research_levels: HashMap<String, u32>,
+    #[serde(default)]
+    pub objective: Objective,
+    /// The cost of consuming one unit of each material, for the cost objective.
+    #[serde(default)]
+    pub costs: HashMap<MaterialPrototype, f64>,
+    /// The weight of each output when maximising the outputs. Outputs default to 1.
+    #[serde(default)]
+    pub output_weights: HashMap<MaterialPrototype, f64>,
+    /// How each material must balance. Materials default to overflowing.
+    #[serde(default)]
+    pub balances: HashMap<MaterialPrototype, MaterialBalance>,
+    /// The time unit rates are entered and shown in.
+    #[serde(default)]
+    pub time_unit: TimeUnit,
+    /// The time unit of the materials overriding `time_unit`.
+    #[serde(default)]
+    pub time_units: HashMap<MaterialPrototype, TimeUnit>,
+    /// The recipe auto-build picks for each material over the others making it. This comes
+    /// before the recipes with the `RecipeRule::Prefer` rule, which are picked for any
+    /// material they make, but a forbidden recipe is never picked.
+    #[serde(default)]
+    pub preferred_recipes: HashMap<MaterialPrototype, String>,
+    /// The rule of each recipe, overriding the rule of its category. Preferred recipes give
+    /// way to the recipe in `preferred_recipes` for a material.
+    #[serde(default)]
+    pub recipe_rules: HashMap<String, RecipeRule>,
+    /// The rule of the recipes of each category. Recipes default to allowed.
+    #[serde(default)]
+    pub category_rules: HashMap<String, RecipeRule>,
+    /// The limit of the outputs and inputs that aren't at least their output amount or at
+    /// most their input amount. The amount is then the value of the limit.
+    #[serde(default)]
+    pub limits: HashMap<MaterialPrototype, Limit>,
+    /// The materials whose net amounts are kept in proportion.
+    #[serde(default)]
+    pub ratios: Vec<Ratio>,
 }
 
 #[derive(Debug)]
 pub enum ModelResult {
-    NoSolution,
-    OneSolution(HashMap<Process, f64>),
+    /// The model can't be solved, for the given reasons.
+    NoSolution(Vec<Infeasibility>),
+    OneSolution(Solution),
     MultipleSolutions {
         lower_bounds: HashMap<MaterialPrototype, f64>,
         higher_bounds: HashMap<MaterialPrototype, f64>,
     },
     Unbounded,
+    /// A solution with whole machine counts, the machines running at full speed as far as
+    /// their ingredients allow, and what they make beyond the outputs.
+    WholeMachines {
+        solution: Solution,
+        overproduction: Vec<(MaterialPrototype, f64)>,
+    },
+    /// The outputs reached with the inputs as hard limits, and the inputs used up to their
+    /// limit.
+    Maximised {
+        solution: Solution,
+        outputs: HashMap<MaterialPrototype, f64>,
+        binding_inputs: Vec<MaterialPrototype>,
+    },
+}
+
+/// The linear program of a model, with a variable per process in the order of its processes.
+struct Problem {
+    variables: ProblemVariables,
+    processes: Vec<Variable>,
+    constraints: Vec<Box<dyn Fn() -> Constraint>>,
+    input_variables: HashMap<MaterialPrototype, Variable>,
+    output_variables: HashMap<MaterialPrototype, Variable>,
+    /// With an elastic problem, how much of each material is missing for it to balance.
+    shortages: HashMap<MaterialPrototype, Variable>,
+    /// With an elastic problem, how much of each balanced material is left over.
+    surpluses: HashMap<MaterialPrototype, Variable>,
+    /// How much of each material is bought in.
+    bought_variables: Vec<Variable>,
+    objective: Expression,
 }
 
 impl Model {
@@ -40,42 +144,122 @@ impl Model {
             .map(|(k, v)| (k.clone(), *v))
     }
 
-    //noinspection DuplicatedCode
-    pub fn solve(&mut self, generate_inputs: bool) -> ModelResult {
+    /// Returns the productivity the researched technologies add.
+    pub fn get_productivity_bonuses(&self) -> ProductivityBonuses {
+        ProductivityBonuses::new(
+            &get_registry(),
+            self.researched.as_ref(),
+            &self.research_levels,
+        )
+    }
+
+    /// Adds a loop recycling the product of `process` until it reaches `quality`, together
+    /// with the processes it needs, and makes the target quality product an output.
+    pub fn add_quality_loop(&mut self, process: &Process, quality: &str) -> Result<(), String> {
+        let quality_loop = QualityLoop::new(process, quality)?;
+        let missing_processes = quality_loop.get_missing_processes(process, &self.processes);
+        self.processes.extend(missing_processes);
+        self.outputs.entry(quality_loop.get_target()).or_insert(0.0);
+        self.quality_loops.push(quality_loop);
+        Ok(())
+    }
+
+    /// Returns the weight of `output` when maximising the outputs.
+    pub fn get_output_weight(&self, output: &MaterialPrototype) -> f64 {
+        self.output_weights.get(output).copied().unwrap_or(1.0)
+    }
+
+    /// Returns the time unit rates of `material` are entered and shown in.
+    pub fn get_time_unit(&self, material: &MaterialPrototype) -> TimeUnit {
+        self.time_units
+            .get(material)
+            .copied()
+            .unwrap_or(self.time_unit)
+    }
+
+    /// Returns how `material` must balance.
+    pub fn get_balance(&self, material: &MaterialPrototype) -> MaterialBalance {
+        self.balances.get(material).copied().unwrap_or_default()
+    }
+
+    /// Builds the linear program of the model. Outputs are lower limits, unless
+    /// `maximise_outputs` is set: they then start at zero, to be maximised. An `elastic`
+    /// problem can always be solved, by taking in or throwing away materials.
+    fn build_problem(
+        &self,
+        generate_inputs: bool,
+        maximise_outputs: bool,
+        elastic: bool,
+    ) -> Problem {
         let mut variables = ProblemVariables::new();
-        let mut processes: HashMap<&Process, Variable> = HashMap::new();
-        let mut materials: HashMap<MaterialPrototype, Vec<&Process>> = HashMap::new();
+        let mut processes: Vec<Variable> = Vec::new();
+        let mut materials: HashMap<MaterialPrototype, Vec<(usize, f64)>> = HashMap::new();
         let mut constraints: Vec<Box<dyn Fn() -> Constraint>> = Vec::new();
         let mut input_variables: HashMap<MaterialPrototype, Variable> = HashMap::new();
         let mut output_variables: HashMap<MaterialPrototype, Variable> = HashMap::new();
+        let mut shortages: HashMap<MaterialPrototype, Variable> = HashMap::new();
+        let mut surpluses: HashMap<MaterialPrototype, Variable> = HashMap::new();
+        // Materials bought in, which unlike inputs have no limit to explore.
+        let mut bought_variables: Vec<Variable> = Vec::new();
+        // The net amount made of each material, negative when consumed.
+        let mut net_amounts: HashMap<MaterialPrototype, Expression> = HashMap::new();
+        let balanced_materials: HashSet<MaterialPrototype> = self
+            .quality_loops
+            .iter()
+            .flat_map(|quality_loop| quality_loop.get_balanced_materials())
+            .chain(
+                self.balances
+                    .iter()
+                    .filter(|&(_, &balance)| balance == MaterialBalance::Balanced)
+                    .map(|(material, _)| material.clone()),
+            )
+            .collect();
+        let loop_inputs: HashSet<MaterialPrototype> = self
+            .quality_loops
+            .iter()
+            .flat_map(|quality_loop| quality_loop.get_input_materials())
+            .collect();
 
-        for process in self.processes.iter() {
-            processes.insert(process, variables.add(variable().min(0)));
-            for material in process
-                .get_ingredients()
-                .into_iter()
-                .chain(process.get_products())
-            {
-                if !materials.contains_key(&material.get_prototype()) {
-                    materials.insert(material.get_prototype(), Vec::new());
-                }
-                materials
-                    .get_mut(&material.get_prototype())
-                    .unwrap()
-                    .push(&process);
+        let surface = self.surface.as_deref();
+        let bonuses = self.get_productivity_bonuses();
+
+        for (index, process) in self.processes.iter().enumerate() {
+            let forbidden = process.process_type == ProcessType::Recipe
+                && self.get_recipe_rule(&process.name) == RecipeRule::Forbid;
+            // A machine limit bounds the crafts per second.
+            let machines_per_craft = process.get_machine_count(1.0, surface);
+            let bounds = process
+                .machine_limit
+                .filter(|_| machines_per_craft > 0.0)
+                .map(|limit| limit.get_bounds())
+                .map(|(lowest, highest)| {
+                    (
+                        lowest / machines_per_craft,
+                        highest.map(|highest| highest / machines_per_craft),
+                    )
+                });
+            processes.push(variables.add(match bounds {
+                _ if forbidden => variable().min(0).max(0),
+                Some((lowest, Some(highest))) => variable().min(lowest).max(highest),
+                Some((lowest, None)) => variable().min(lowest),
+                None => variable().min(0),
+            }));
+            for (material, amount) in process.get_net_amounts(surface, &bonuses) {
+                materials.entry(material).or_default().push((index, amount));
             }
             #[cfg(all(debug_assertions, feature = "debug_model"))]
-            println!(
-                "{:#?} : {:#?}",
-                process.name.clone(),
-                processes.get(process).unwrap()
-            );
+            println!("{:#?} : {:#?}", process.name.clone(), processes[index]);
         }
 
-        for (prototype, &amount) in self.inputs.iter() {
+        for prototype in self.inputs.keys() {
+            let (lowest, highest) = self.get_input_limit(prototype).get_bounds();
+            let variable = variable().min(lowest);
             input_variables.insert(
                 prototype.clone(),
-                variables.add(variable().min(0).max(amount)),
+                variables.add(match highest {
+                    Some(highest) => variable.max(highest),
+                    None => variable,
+                }),
             );
             #[cfg(all(debug_assertions, feature = "debug_model"))]
             println!(
@@ -85,8 +269,18 @@ impl Model {
             );
         }
 
-        for (prototype, &amount) in self.outputs.iter() {
-            output_variables.insert(prototype.clone(), variables.add(variable().min(amount)));
+        for prototype in self.outputs.keys() {
+            // Outputs no process makes still need a constraint to be unreachable.
+            materials.entry(prototype.clone()).or_default();
+            let (lowest, highest) = self.get_output_limit(prototype).get_bounds();
+            let variable = variable().min(if maximise_outputs { 0.0 } else { lowest });
+            output_variables.insert(
+                prototype.clone(),
+                variables.add(match highest {
+                    Some(highest) => variable.max(highest),
+                    None => variable,
+                }),
+            );
             #[cfg(all(debug_assertions, feature = "debug_model"))]
             println!(
                 "output {:#?} : {:#?}",
@@ -100,36 +294,19 @@ impl Model {
             println!("{:#?}", &material_prototype);
             let mut expression = Expression::from(0);
             let mut generate_input: bool = true;
-            for &process in material_processes {
+            for &(index, amount) in material_processes {
                 #[cfg(all(debug_assertions, feature = "debug_model"))]
-                println!("{:#?}", &process.name.clone());
-                for ingredient in process.get_ingredients() {
-                    if ingredient.get_prototype() == *material_prototype {
-                        expression -= *processes.get(process).unwrap();
-                        #[cfg(all(debug_assertions, feature = "debug_model"))]
-                        println!(
-                            "{:#?}",
-                            ingredient.get_average_amount(process.productivity as f64)
-                        );
-                    }
-                }
-                for product in process.get_products() {
-                    if product.get_prototype() == *material_prototype {
-                        generate_input = false;
-                        expression += *processes.get(process).unwrap()
-                            * product.get_average_amount(process.productivity as f64);
-                        #[cfg(all(debug_assertions, feature = "debug_model"))]
-                        println!(
-                            "{:#?}",
-                            product.get_average_amount(process.productivity as f64)
-                        );
-                    }
+                println!("{:#?} : {:#?}", &self.processes[index].name, amount);
+                if amount > 0.0 {
+                    generate_input = false;
                 }
+                expression += processes[index] * amount;
             }
             if generate_inputs
-                && generate_input
-                && !self.inputs.contains_key(&material_prototype)
-                && !self.outputs.contains_key(&material_prototype)
+                && (generate_input || loop_inputs.contains(material_prototype))
+                && !balanced_materials.contains(material_prototype)
+                && !self.inputs.contains_key(material_prototype)
+                && !self.outputs.contains_key(material_prototype)
             {
                 input_variables
                     .insert(material_prototype.clone(), variables.add(variable().min(0)));
@@ -140,15 +317,23 @@ impl Model {
                     input_variables.get(material_prototype).unwrap()
                 );
             }
+            if self.get_balance(material_prototype) == MaterialBalance::BuyIn
+                && !input_variables.contains_key(material_prototype)
+            {
+                let bought = variables.add(variable().min(0));
+                expression += bought;
+                bought_variables.push(bought);
+            }
             #[cfg(all(debug_assertions, feature = "debug_model"))]
             println!("{:#?}", &expression);
+            net_amounts.insert(material_prototype.clone(), expression.clone());
             let limit: Expression = match (
-                output_variables.get(&material_prototype),
-                input_variables.get(&material_prototype),
+                output_variables.get(material_prototype),
+                input_variables.get(material_prototype),
             ) {
                 (Some(output), Some(input)) => {
-                    if -self.inputs.get(&material_prototype).unwrap()
-                        < *self.outputs.get(&material_prototype).unwrap()
+                    if -self.inputs.get(material_prototype).unwrap()
+                        < *self.outputs.get(material_prototype).unwrap()
                     {
                         -input.into_expression()
                     } else {
@@ -161,40 +346,113 @@ impl Model {
             };
             #[cfg(all(debug_assertions, feature = "debug_model"))]
             println!("{:#?}", limit);
-            constraints.push(Box::new(move || expression.clone().geq(limit.clone())));
+            // Outputs and inputs limited otherwise than by default can't overflow either.
+            let exact = self.outputs.contains_key(material_prototype)
+                && !matches!(self.get_output_limit(material_prototype), Limit::Minimum(_))
+                || self.inputs.contains_key(material_prototype)
+                    && !matches!(self.get_input_limit(material_prototype), Limit::Maximum(_));
+            if elastic {
+                let shortage = variables.add(variable().min(0));
+                expression += shortage;
+                shortages.insert(material_prototype.clone(), shortage);
+                if balanced_materials.contains(material_prototype) || exact {
+                    let surplus = variables.add(variable().min(0));
+                    expression -= surplus;
+                    surpluses.insert(material_prototype.clone(), surplus);
+                }
+            }
+            if balanced_materials.contains(material_prototype) || exact {
+                constraints.push(Box::new(move || expression.clone().eq(limit.clone())));
+            } else {
+                constraints.push(Box::new(move || expression.clone().geq(limit.clone())));
+            }
         }
 
+        for ratio in self.ratios.iter() {
+            constraints.extend(ratio.get_constraints(&net_amounts));
+        }
+
+        let objective = self.processes.iter().zip(processes.iter()).fold(
+            Expression::from(0),
+            |acc, (process, variable)| {
+                acc + *variable
+                    * self
+                        .objective
+                        .get_process_weight(process, surface, &bonuses, &self.costs)
+            },
+        ) + input_variables
+            .iter()
+            .fold(Expression::from(0), |acc, (material, variable)| {
+                acc + *variable * self.objective.get_input_weight(material)
+            });
+
+        Problem {
+            variables,
+            processes,
+            constraints,
+            input_variables,
+            output_variables,
+            shortages,
+            surpluses,
+            bought_variables,
+            objective,
+        }
+    }
+
+    //noinspection DuplicatedCode
+    pub fn solve(&mut self, generate_inputs: bool) -> ModelResult {
+        let Problem {
+            variables,
+            processes,
+            mut constraints,
+            input_variables,
+            output_variables,
+            bought_variables,
+            objective,
+            ..
+        } = self.build_problem(generate_inputs, false, false);
+
+        if buy_least(&variables, &mut constraints, &bought_variables).is_err() {
+            return ModelResult::NoSolution(self.diagnose(generate_inputs, false));
+        }
         let results = copy_variables(&variables)
-            .minimise(
-                processes
-                    .iter()
-                    .fold(Expression::from(0), |acc, process| acc + process.1),
-            )
+            .minimise(objective)
             .using(microlp)
             .with_all(constraints.iter().map(|function| function()))
             .solve();
 
-        if results.is_err()
-            || match results.as_ref().unwrap().status() {
-                Optimal => false,
-                _ => true,
-            }
-        {
-            return ModelResult::NoSolution;
+        if results.is_err() || !matches!(results.as_ref().unwrap().status(), Optimal) {
+            return ModelResult::NoSolution(self.diagnose(generate_inputs, false));
+        }
+
+        // What is bought in is kept to the least, not explored like the inputs.
+        for bought in bought_variables {
+            let amount = results.as_ref().unwrap().value(bought);
+            constraints.push(Box::new(move || {
+                bought.into_expression().leq(amount + 1e-9)
+            }));
         }
 
         let mut multiple_solutions: bool = false;
+        // Generated or bought in materials put no limit on the outputs.
+        let bounded = !generate_inputs
+            && !self
+                .balances
+                .values()
+                .any(|&balance| balance == MaterialBalance::BuyIn);
 
         let mut input_optimums: HashMap<MaterialPrototype, f64> = HashMap::new();
         let mut output_optimums: HashMap<MaterialPrototype, f64> = HashMap::new();
 
         for (material, variable) in input_variables.iter() {
-            let (min, max_opt) = match solve_for(&variables, &constraints, variable, !generate_inputs) {
+            let (min, max_opt) = match solve_for(&variables, &constraints, variable, bounded) {
                 Ok(result) => result,
                 Err(err) => {
                     return match err {
                         ResolutionError::Unbounded => ModelResult::Unbounded,
-                        ResolutionError::Infeasible => ModelResult::NoSolution,
+                        ResolutionError::Infeasible => {
+                            ModelResult::NoSolution(self.diagnose(generate_inputs, false))
+                        }
                         _ => panic!(),
                     }
                 }
@@ -202,42 +460,46 @@ impl Model {
             if generate_inputs {
                 self.inputs.insert(material.clone(), min);
             }
-            if let Some(max) = max_opt {
-                if (max - min).abs() > 1e-6 {
-                    multiple_solutions = true;
-                    input_optimums.insert(material.clone(), min);
-                }
+            if let Some(max) = max_opt
+                && (max - min).abs() > 1e-6
+            {
+                multiple_solutions = true;
+                input_optimums.insert(material.clone(), min);
             }
         }
 
         for (material, variable) in output_variables.iter() {
-            let (min, max_opt) = match solve_for(&variables, &constraints, variable, !generate_inputs) {
+            let (min, max_opt) = match solve_for(&variables, &constraints, variable, bounded) {
                 Ok(result) => result,
                 Err(err) => {
                     return match err {
                         ResolutionError::Unbounded => ModelResult::Unbounded,
-                        ResolutionError::Infeasible => ModelResult::NoSolution,
+                        ResolutionError::Infeasible => {
+                            ModelResult::NoSolution(self.diagnose(generate_inputs, false))
+                        }
                         _ => panic!(),
                     }
                 }
             };
-            if let Some(max) = max_opt {
-                if (max - min).abs() > 1e-6 {
-                    multiple_solutions = true;
-                    output_optimums.insert(material.clone(), max);
-                }
+            if let Some(max) = max_opt
+                && (max - min).abs() > 1e-6
+            {
+                multiple_solutions = true;
+                output_optimums.insert(material.clone(), max);
             }
         }
 
         if !multiple_solutions {
-            return ModelResult::OneSolution(
-                processes
+            return ModelResult::OneSolution(Solution::new(
+                self.processes
                     .iter()
-                    .map(|(&process, variable)| {
-                        (process.clone(), results.as_ref().unwrap().value(*variable))
-                    })
-                    .collect(),
-            );
+                    .zip(processes)
+                    .map(|(process, variable)| {
+                        (process.clone(), results.as_ref().unwrap().value(variable))
+                    }),
+                self.surface.as_deref(),
+                &self.get_productivity_bonuses(),
+            ));
         }
 
         ModelResult::MultipleSolutions {
@@ -245,6 +507,88 @@ impl Model {
             higher_bounds: output_optimums,
         }
     }
+
+    /// Solves the model with the inputs as hard limits, maximising the outputs: their weighted
+    /// sum, or with `fixed_ratios` their amounts scaled by a common factor. The objective then
+    /// picks among the solutions reaching the maximum.
+    pub fn maximise(&self, fixed_ratios: bool) -> ModelResult {
+        let Problem {
+            mut variables,
+            processes,
+            mut constraints,
+            input_variables,
+            output_variables,
+            bought_variables,
+            objective,
+            ..
+        } = self.build_problem(false, true, false);
+
+        let target = if fixed_ratios {
+            let scale = variables.add(variable().min(0));
+            for (material, &output) in output_variables.iter() {
+                let ratio = *self.outputs.get(material).unwrap();
+                constraints.push(Box::new(move || output.into_expression().eq(scale * ratio)));
+            }
+            scale.into_expression()
+        } else {
+            output_variables
+                .iter()
+                .fold(Expression::from(0), |acc, (material, variable)| {
+                    acc + *variable * self.get_output_weight(material)
+                })
+        };
+        let maximum = match copy_variables(&variables)
+            .maximise(target.clone())
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+        {
+            Ok(results) => results.eval(target.clone()),
+            Err(ResolutionError::Unbounded) => return ModelResult::Unbounded,
+            Err(_) => return ModelResult::NoSolution(self.diagnose(false, true)),
+        };
+
+        let minimum = maximum - 1e-9 * maximum.abs().max(1.0);
+        constraints.push(Box::new(move || target.clone().geq(minimum)));
+        if buy_least(&variables, &mut constraints, &bought_variables).is_err() {
+            return ModelResult::NoSolution(self.diagnose(false, true));
+        }
+        let Ok(results) = copy_variables(&variables)
+            .minimise(objective)
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+        else {
+            return ModelResult::NoSolution(self.diagnose(false, true));
+        };
+
+        let mut binding_inputs: Vec<MaterialPrototype> = input_variables
+            .iter()
+            .filter(|(material, variable)| {
+                self.get_input_limit(material)
+                    .get_bounds()
+                    .1
+                    .is_some_and(|highest| results.value(**variable) >= highest - 1e-6)
+            })
+            .map(|(material, _)| material.clone())
+            .collect();
+        binding_inputs.sort_by_key(|material| material.to_id());
+        ModelResult::Maximised {
+            solution: Solution::new(
+                self.processes
+                    .iter()
+                    .zip(processes)
+                    .map(|(process, variable)| (process.clone(), results.value(variable))),
+                self.surface.as_deref(),
+                &self.get_productivity_bonuses(),
+            ),
+            outputs: output_variables
+                .iter()
+                .map(|(material, variable)| (material.clone(), results.value(*variable)))
+                .collect(),
+            binding_inputs,
+        }
+    }
 }
 
 fn copy_variables(variables: &ProblemVariables) -> ProblemVariables {
@@ -255,29 +599,53 @@ fn copy_variables(variables: &ProblemVariables) -> ProblemVariables {
     new_variables
 }
 
+/// Limits what is bought in to the least the constraints allow, so that the objective
+/// minimised next never buys a material it could make, whatever making it costs.
+fn buy_least(
+    variables: &ProblemVariables,
+    constraints: &mut Vec<Box<dyn Fn() -> Constraint>>,
+    bought_variables: &[Variable],
+) -> Result<(), ResolutionError> {
+    if bought_variables.is_empty() {
+        return Ok(());
+    }
+    let bought = bought_variables
+        .iter()
+        .fold(Expression::from(0), |acc, variable| acc + *variable);
+    let least = copy_variables(variables)
+        .minimise(bought.clone())
+        .using(microlp)
+        .with_all(constraints.iter().map(|function| function()))
+        .solve()?
+        .eval(bought.clone());
+    let most = least + 1e-9 * least.abs().max(1.0);
+    constraints.push(Box::new(move || bought.clone().leq(most)));
+    Ok(())
+}
+
 fn solve_for(
     variables: &ProblemVariables,
     constraints: &Vec<Box<dyn Fn() -> Constraint>>,
     variable: &Variable,
     maximize: bool,
 ) -> Result<(f64, Option<f64>), ResolutionError> {
-    let min = copy_variables(&variables)
+    let min = copy_variables(variables)
         .minimise(variable)
         .using(microlp)
         .with_all(constraints.iter().map(|function| function()))
         .solve()?
         .value(*variable);
-    let max = copy_variables(&variables)
+    let max = copy_variables(variables)
         .maximise(variable)
         .using(microlp)
         .with_all(constraints.iter().map(|function| function()))
         .solve()
-        .and_then(|result| Ok(Some(result.value(*variable))))
+        .map(|result| Some(result.value(*variable)))
         .or_else(|error| {
             if let ResolutionError::Unbounded = error
                 && !maximize
             {
-                return Ok(None);
+                Ok(None)
             } else {
                 Err(error)
             }
@@ -289,17 +657,458 @@ fn solve_for(
 mod tests {
     use crate::data::data_loader::load_data;
     use crate::data::materials::MaterialPrototype;
-    use crate::model::Model;
+    use crate::data::quality::NORMAL_QUALITY;
+    use crate::data::technologies::ProductivityBonuses;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::limit::Limit;
+    use crate::model::{Model, ModelResult};
     use std::collections::HashMap;
 
+    const CATALYST_RECIPES: &str = r#"{
+        "recipe": {
+            "kovarex-enrichment-process": {
+                "name": "kovarex-enrichment-process",
+                "category": "centrifuging",
+                "energy_required": 60,
+                "ingredients": [
+                    {"type": "item", "name": "uranium-235", "amount": 40},
+                    {"type": "item", "name": "uranium-238", "amount": 5}
+                ],
+                "results": [
+                    {"type": "item", "name": "uranium-235", "amount": 41, "ignored_by_productivity": 40},
+                    {"type": "item", "name": "uranium-238", "amount": 2, "ignored_by_productivity": 2}
+                ]
+            },
+            "coal-liquefaction": {
+                "name": "coal-liquefaction",
+                "category": "oil-processing",
+                "energy_required": 5,
+                "allow_productivity": true,
+                "ingredients": [
+                    {"type": "item", "name": "coal", "amount": 10},
+                    {"type": "fluid", "name": "heavy-oil", "amount": 25},
+                    {"type": "fluid", "name": "steam", "amount": 50}
+                ],
+                "results": [
+                    {"type": "fluid", "name": "heavy-oil", "amount": 90, "ignored_by_productivity": 25},
+                    {"type": "fluid", "name": "light-oil", "amount": 20},
+                    {"type": "fluid", "name": "petroleum-gas", "amount": 10}
+                ]
+            }
+        },
+        "assembling-machine": {
+            "centrifuge": {
+                "name": "centrifuge",
+                "energy_usage": "350kW",
+                "crafting_speed": 1,
+                "crafting_categories": ["centrifuging"],
+                "energy_source": {"type": "electric"}
+            },
+            "oil-refinery": {
+                "name": "oil-refinery",
+                "energy_usage": "420kW",
+                "crafting_speed": 1,
+                "crafting_categories": ["oil-processing"],
+                "energy_source": {"type": "electric", "emissions_per_minute": {"pollution": 6}},
+                "allowed_effects": ["productivity", "consumption", "pollution"],
+                "allowed_module_categories": ["productivity"],
+                "module_slots": 1
+            }
+        },
+        "module": {
+            "productivity-module": {
+                "name": "productivity-module",
+                "stack_size": 50,
+                "category": "productivity",
+                "effect": {"productivity": 0.1, "consumption": 0.8, "pollution": 0.1}
+            }
+        }
+    }"#;
+
+    fn item(name: &str) -> MaterialPrototype {
+        MaterialPrototype::Item(name.into(), NORMAL_QUALITY.into())
+    }
+
+    fn fluid(name: &str) -> MaterialPrototype {
+        MaterialPrototype::Fluid(name.into())
+    }
+
+    fn assert_amount(
+        amounts: &HashMap<MaterialPrototype, f64>,
+        material: MaterialPrototype,
+        expected: f64,
+    ) {
+        let amount = amounts.get(&material).copied().unwrap_or(0.0);
+        assert!(
+            (amount - expected).abs() < 1e-6,
+            "{:?} : {} != {}",
+            material,
+            amount,
+            expected
+        );
+    }
+
+    #[test]
+    fn net_amounts_respect_catalysts() {
+        let _registry = set_test_registry(CATALYST_RECIPES);
+        let kovarex = Process {
+            machine: Some("centrifuge".into()),
+            ..Process::new(ProcessType::Recipe, "kovarex-enrichment-process".into())
+        };
+        let amounts = kovarex.get_net_amounts(None, &ProductivityBonuses::default());
+        assert_amount(&amounts, item("uranium-235"), 1.0);
+        assert_amount(&amounts, item("uranium-238"), -3.0);
+
+        // productivity only applies to the 65 heavy oil that isn't a catalyst
+        let liquefaction = Process {
+            machine: Some("oil-refinery".into()),
+            modules: vec!["productivity-module".into()],
+            ..Process::new(ProcessType::Recipe, "coal-liquefaction".into())
+        };
+        let amounts = liquefaction.get_net_amounts(None, &ProductivityBonuses::default());
+        assert_amount(&amounts, item("coal"), -10.0);
+        assert_amount(&amounts, fluid("steam"), -50.0);
+        assert_amount(&amounts, fluid("heavy-oil"), 71.5);
+        assert_amount(&amounts, fluid("light-oil"), 22.0);
+        assert_amount(&amounts, fluid("petroleum-gas"), 11.0);
+
+        // 6/min for 5s, scaled by the consumption and pollution of the module
+        let emissions = liquefaction.get_emissions(None);
+        assert!((emissions.get("pollution").unwrap() - 6.0 * 1.8 * 1.1 * 5.0 / 60.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reports_emissions() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "bioflux": {
+                        "name": "bioflux",
+                        "category": "organic",
+                        "energy_required": 3.2,
+                        "emissions_multiplier": 2,
+                        "ingredients": [],
+                        "results": [{"type": "item", "name": "bioflux", "amount": 1}]
+                    }
+                },
+                "assembling-machine": {
+                    "biochamber": {
+                        "name": "biochamber",
+                        "energy_usage": "500kW",
+                        "crafting_speed": 2,
+                        "crafting_categories": ["organic"],
+                        "energy_source": {
+                            "type": "void",
+                            "emissions_per_minute": {"pollution": 4, "spores": 2}
+                        },
+                        "allowed_effects": ["speed", "consumption"],
+                        "allowed_module_categories": ["speed"],
+                        "module_slots": 1
+                    }
+                },
+                "module": {
+                    "speed-module": {
+                        "name": "speed-module",
+                        "stack_size": 50,
+                        "category": "speed",
+                        "effect": {"speed": 0.5, "consumption": 0.7}
+                    }
+                }
+            }"#,
+        );
+        let plain = Process {
+            machine: Some("biochamber".into()),
+            ..Process::new(ProcessType::Recipe, "bioflux".into())
+        };
+        // 1.6 s per craft, doubled by the recipe
+        let emissions = plain.get_emissions(None);
+        assert!((emissions["pollution"] - 4.0 * 1.6 / 60.0 * 2.0).abs() < 1e-6);
+        assert!((emissions["spores"] - 2.0 * 1.6 / 60.0 * 2.0).abs() < 1e-6);
+
+        // the module speeds crafts up to 3.2 / 3 s, each using 70% more energy
+        let sped_up = Process {
+            modules: vec!["speed-module".into()],
+            ..plain
+        };
+        let emissions = sped_up.get_emissions(None);
+        let seconds = 3.2 / 3.0;
+        assert!((emissions["pollution"] - 4.0 * 1.7 * seconds / 60.0 * 2.0).abs() < 1e-6);
+        assert!((emissions["spores"] - 2.0 * 1.7 * seconds / 60.0 * 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn solves_with_ingredient_amounts() {
+        let _registry = set_test_registry(CATALYST_RECIPES);
+        let mut model = Model {
+            processes: vec![
+                Process {
+                    machine: Some("centrifuge".into()),
+                    ..Process::new(ProcessType::Recipe, "kovarex-enrichment-process".into())
+                },
+                Process {
+                    machine: Some("oil-refinery".into()),
+                    ..Process::new(ProcessType::Recipe, "coal-liquefaction".into())
+                },
+            ],
+            outputs: HashMap::from([(item("uranium-235"), 2.0), (fluid("petroleum-gas"), 1.0)]),
+            ..Default::default()
+        };
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        for process in solution.processes {
+            let (process, crafts) = (process.process, process.crafts);
+            let expected = match process.name.as_str() {
+                "kovarex-enrichment-process" => 2.0,
+                _ => 0.1,
+            };
+            assert!(
+                (crafts - expected).abs() < 1e-6,
+                "{} : {}",
+                process.name,
+                crafts
+            );
+        }
+        assert_amount(&model.inputs, item("uranium-238"), 6.0);
+        assert_amount(&model.inputs, item("coal"), 1.0);
+        assert_amount(&model.inputs, fluid("steam"), 5.0);
+        assert!(!model.inputs.contains_key(&item("uranium-235")));
+        assert!(!model.inputs.contains_key(&fluid("heavy-oil")));
+    }
+
+    #[test]
+    fn solves_process_instances() {
+        let _registry = set_test_registry(CATALYST_RECIPES);
+        let kovarex = Process {
+            machine: Some("centrifuge".into()),
+            ..Process::new(ProcessType::Recipe, "kovarex-enrichment-process".into())
+        };
+        // 30 centrifuges make half a craft per second
+        let fixed = Process {
+            label: Some("2".into()),
+            machine_limit: Some(Limit::Exact(30.0)),
+            ..kovarex.clone()
+        };
+        assert_ne!(kovarex, fixed);
+        let mut model = Model {
+            processes: vec![kovarex, fixed],
+            outputs: HashMap::from([(item("uranium-235"), 2.0)]),
+            ..Default::default()
+        };
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        let crafts: Vec<f64> = solution
+            .processes
+            .iter()
+            .map(|process| process.crafts)
+            .collect();
+        assert!((crafts[0] - 1.5).abs() < 1e-6, "{:?}", crafts);
+        assert!((crafts[1] - 0.5).abs() < 1e-6, "{:?}", crafts);
+        assert!((solution.processes[1].machines - 30.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sizes_power_plant() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 1,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                },
+                "assembling-machine": {
+                    "assembling-machine": {
+                        "name": "assembling-machine",
+                        "energy_usage": "150kW",
+                        "crafting_speed": 1,
+                        "crafting_categories": ["crafting"],
+                        "energy_source": {"type": "electric", "drain": "5kW"}
+                    }
+                },
+                "solar-panel": {
+                    "solar-panel": {"name": "solar-panel", "production": "60kW"}
+                }
+            }"#,
+        );
+        let mut model = Model {
+            processes: vec![
+                Process {
+                    machine: Some("assembling-machine".into()),
+                    ..Process::new(ProcessType::Recipe, "iron-gear-wheel".into())
+                },
+                Process::new(ProcessType::Generator, "solar-panel".into()),
+            ],
+            outputs: HashMap::from([(item("iron-gear-wheel"), 1.0)]),
+            ..Default::default()
+        };
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        let panels = solution
+            .processes
+            .iter()
+            .find(|process| process.process.process_type == ProcessType::Generator)
+            .unwrap()
+            .crafts;
+        // 0.155 MW used, 0.042 MW made by each panel on average
+        assert!((panels - 0.155 / 0.042).abs() < 1e-6, "{}", panels);
+        assert!(!model.inputs.contains_key(&MaterialPrototype::Electricity));
+    }
+
+    #[test]
+    fn burns_fuel() {
+        let _registry = set_test_registry(
+            r#"{
+                "item": {
+                    "coal": {"name": "coal", "stack_size": 50, "fuel_category": "chemical", "fuel_value": "4MJ"},
+                    "fuel-cell": {
+                        "name": "fuel-cell",
+                        "stack_size": 50,
+                        "fuel_category": "chemical",
+                        "fuel_value": "8MJ",
+                        "burnt_result": "depleted-cell"
+                    },
+                    "depleted-cell": {"name": "depleted-cell", "stack_size": 50}
+                },
+                "fluid": {
+                    "water": {"name": "water", "default_temperature": 15, "heat_capacity": "0.2kJ"},
+                    "steam": {"name": "steam", "default_temperature": 15, "heat_capacity": "0.2kJ"}
+                },
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "category": "smelting",
+                        "energy_required": 3.2,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    }
+                },
+                "furnace": {
+                    "stone-furnace": {
+                        "name": "stone-furnace",
+                        "energy_usage": "90kW",
+                        "crafting_speed": 2,
+                        "crafting_categories": ["smelting"],
+                        "energy_source": {"type": "burner", "fuel_categories": ["chemical"]}
+                    }
+                },
+                "boiler": {
+                    "boiler": {
+                        "name": "boiler",
+                        "energy_consumption": "1.8MW",
+                        "target_temperature": 165,
+                        "fluid_box": {"filter": "water"},
+                        "output_fluid_box": {"filter": "steam"},
+                        "energy_source": {"type": "burner", "effectivity": 0.5, "fuel_categories": ["chemical"]}
+                    }
+                }
+            }"#,
+        );
+        let mut model = Model {
+            processes: vec![
+                Process {
+                    machine: Some("stone-furnace".into()),
+                    fuel: Some("coal".into()),
+                    ..Process::new(ProcessType::Recipe, "iron-plate".into())
+                },
+                Process {
+                    fuel: Some("fuel-cell".into()),
+                    ..Process::new(ProcessType::Generator, "boiler".into())
+                },
+            ],
+            outputs: HashMap::from([(item("iron-plate"), 1.0), (fluid("steam"), 60.0)]),
+            ..Default::default()
+        };
+        let ModelResult::OneSolution(_solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        // 90kW for 1.6s per plate
+        assert_amount(&model.inputs, item("coal"), 0.036);
+        // 1.8MW at 50% effectivity heats 60 water per second into steam
+        assert_amount(&model.inputs, fluid("water"), 60.0);
+        assert_amount(&model.inputs, item("fuel-cell"), 0.45);
+        let boiler = &model.processes[1];
+        assert_amount(
+            &boiler.get_net_amounts(None, &ProductivityBonuses::default()),
+            item("depleted-cell"),
+            0.45,
+        );
+    }
+
+    #[test]
+    fn maximises_outputs() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    },
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let mut model = Model {
+            processes: vec![
+                Process::new(ProcessType::Recipe, "iron-plate".into()),
+                Process::new(ProcessType::Recipe, "iron-gear-wheel".into()),
+            ],
+            inputs: HashMap::from([(item("iron-ore"), 10.0), (item("coal"), 5.0)]),
+            outputs: HashMap::from([(item("iron-plate"), 1.0), (item("iron-gear-wheel"), 1.0)]),
+            ..Default::default()
+        };
+
+        let ModelResult::Maximised {
+            outputs,
+            binding_inputs,
+            ..
+        } = model.maximise(true)
+        else {
+            panic!("the outputs should be maximised");
+        };
+        assert_amount(&outputs, item("iron-plate"), 10.0 / 3.0);
+        assert_amount(&outputs, item("iron-gear-wheel"), 10.0 / 3.0);
+        assert_eq!(binding_inputs, [item("iron-ore")]);
+
+        // an input at the floor of its range doesn't hold the outputs back
+        for limit in [Limit::Range(5.0, 20.0), Limit::Minimum(5.0)] {
+            model.limits.insert(item("coal"), limit);
+            let ModelResult::Maximised { binding_inputs, .. } = model.maximise(true) else {
+                panic!("the outputs should be maximised");
+            };
+            assert_eq!(binding_inputs, [item("iron-ore")]);
+        }
+        model.limits.clear();
+
+        model.output_weights.insert(item("iron-gear-wheel"), 3.0);
+        let ModelResult::Maximised { outputs, .. } = model.maximise(false) else {
+            panic!("the outputs should be maximised");
+        };
+        assert_amount(&outputs, item("iron-plate"), 0.0);
+        assert_amount(&outputs, item("iron-gear-wheel"), 5.0);
+    }
+
     #[test]
     fn test() {
-        let _registry =
+        let (_registry, _warnings) =
             load_data("E:/Games/Factorio/script-output/data-raw-dump.json".to_string()).unwrap();
         let mut model: Model = Model {
             processes: vec![],
             inputs: HashMap::new(),
-            outputs: HashMap::from([(MaterialPrototype::Item("rocket-part".to_string()), 200.0)]),
+            outputs: HashMap::from([(
+                MaterialPrototype::Item("rocket-part".to_string(), NORMAL_QUALITY.to_string()),
+                200.0,
+            )]),
+            ..Default::default()
         };
         println!("{:?}", model.solve(true));
         println!("{:#?}", model.inputs);
@@ -0,0 +1,314 @@
+use crate::data::materials::{Material, MaterialPrototype};
+use crate::data::quality::NORMAL_QUALITY;
+use crate::data::technologies::is_recipe_unlocked;
+use crate::data::{get_registry, Process, ProcessType};
+use crate::model::balance::MaterialBalance;
+use crate::model::recipe_rules::RecipeRule;
+use crate::model::Model;
+use std::collections::{HashSet, VecDeque};
+use tap::Tap;
+
+impl Model {
+    /// Returns the available recipes making `material` without consuming it, forbidden ones
+    /// left out, the likeliest first: preferred recipes, the recipe named after the material,
+    /// then those with the fewest products and ingredients.
+    pub fn get_recipes_making(&self, material: &MaterialPrototype) -> Vec<String> {
+        let registry = get_registry();
+        let quality = match material {
+            MaterialPrototype::Item(_, quality) => quality.as_str(),
+            _ => NORMAL_QUALITY,
+        };
+        let name = match material {
+            MaterialPrototype::Item(name, _) | MaterialPrototype::Fluid(name) => name.as_str(),
+            _ => "",
+        };
+        let mut recipes: Vec<(bool, bool, usize, usize, String)> = registry
+            .recipes
+            .values()
+            .filter(|recipe| is_recipe_unlocked(&registry, self.researched.as_ref(), &recipe.name))
+            .filter_map(|recipe| {
+                let rule = self.get_recipe_rule(&recipe.name);
+                if rule == RecipeRule::Forbid {
+                    return None;
+                }
+                let process = Process {
+                    quality: quality.into(),
+                    ..Process::new(ProcessType::Recipe, recipe.name.clone())
+                };
+                let makes = |materials: Vec<Material>| {
+                    materials
+                        .iter()
+                        .any(|other| other.get_prototype() == *material)
+                };
+                (makes(process.get_products())
+                    && !makes(process.get_ingredients())
+                    && process.can_run_on(self.surface.as_deref()))
+                .then(|| {
+                    (
+                        rule != RecipeRule::Prefer,
+                        recipe.name != name,
+                        recipe.results.len(),
+                        recipe.ingredients.len(),
+                        recipe.name.clone(),
+                    )
+                })
+            })
+            .collect();
+        recipes.sort();
+        recipes.into_iter().map(|(_, _, _, _, name)| name).collect()
+    }
+
+    /// Adds the processes the outputs need, walking back from them to raw resources. A
+    /// material is made by the recipe preferred for it, else by a preferred recipe, else by
+    /// a resource or a plant yielding it, else by the likeliest allowed recipe. With
+    /// `alternatives`, a material without a recipe preferred for it is made by the resource
+    /// or plant and every allowed recipe at once, for the solver to pick the cheapest mix.
+    /// Materials already made by a process, inputs and materials bought in are left as they
+    /// are. Each new process gets the fastest machine that can run it. Returns the materials
+    /// nothing could be found to make, sorted.
+    pub fn auto_build(&mut self, alternatives: bool) -> Vec<MaterialPrototype> {
+        let mut made: HashSet<MaterialPrototype> = self
+            .processes
+            .iter()
+            .flat_map(|process| process.get_products())
+            .map(|material| material.get_prototype())
+            .chain(
+                self.processes
+                    .iter()
+                    .filter(|process| process.process_type == ProcessType::Research)
+                    .map(|process| MaterialPrototype::Research(process.name.clone())),
+            )
+            .collect();
+        let mut queue: VecDeque<MaterialPrototype> = self
+            .outputs
+            .keys()
+            .cloned()
+            .collect::<Vec<MaterialPrototype>>()
+            .tap_mut(|vec| vec.sort_by_key(|material| material.to_id()))
+            .into();
+        let mut unmade: Vec<MaterialPrototype> = vec![];
+        while let Some(material) = queue.pop_front() {
+            if made.contains(&material)
+                || self.inputs.contains_key(&material)
+                || self.get_balance(&material) == MaterialBalance::BuyIn
+                || material == MaterialPrototype::Electricity
+            {
+                continue;
+            }
+            made.insert(material.clone());
+            let processes = self.find_processes_making(&material, alternatives);
+            if processes.is_empty() {
+                unmade.push(material);
+                continue;
+            }
+            let surface = self.surface.as_deref();
+            for mut process in processes {
+                process.machine = process
+                    .get_machine_choices(surface)
+                    .into_iter()
+                    .map(|machine| {
+                        let speed = Process {
+                            machine: Some(machine.clone()),
+                            ..process.clone()
+                        }
+                        .get_speed(surface);
+                        (machine, speed)
+                    })
+                    .max_by(|(a, a_speed), (b, b_speed)| a_speed.total_cmp(b_speed).then(b.cmp(a)))
+                    .map(|(machine, _)| machine);
+                for ingredient in process.get_ingredients() {
+                    queue.push_back(ingredient.get_prototype());
+                }
+                for product in process.get_products() {
+                    made.insert(product.get_prototype());
+                }
+                self.processes.push(process);
+            }
+        }
+        unmade.sort_by_key(|material| material.to_id());
+        unmade
+    }
+
+    /// Returns the new processes making `material`, see `auto_build`.
+    fn find_processes_making(
+        &self,
+        material: &MaterialPrototype,
+        alternatives: bool,
+    ) -> Vec<Process> {
+        let registry = get_registry();
+        if let MaterialPrototype::Research(technology) = material {
+            return registry
+                .technologies
+                .get(technology)
+                .filter(|technology| technology.unit.is_some())
+                .map(|_| Process::new(ProcessType::Research, technology.clone()))
+                .into_iter()
+                .collect();
+        }
+        let recipes = self.get_recipes_making(material);
+        let process = |recipe: &String| Process {
+            quality: match material {
+                MaterialPrototype::Item(_, quality) => quality.clone(),
+                _ => NORMAL_QUALITY.into(),
+            },
+            ..Process::new(ProcessType::Recipe, recipe.clone())
+        };
+        if let Some(recipe) = self
+            .preferred_recipes
+            .get(material)
+            .filter(|recipe| recipes.contains(recipe))
+        {
+            return vec![process(recipe)];
+        }
+        let surface = self.surface.as_deref();
+        let yields = |process: &Process| {
+            process
+                .get_products()
+                .iter()
+                .any(|product| product.get_prototype() == *material)
+                && process.can_run_on(surface)
+        };
+        let mut raw: Vec<Process> = registry
+            .resources
+            .keys()
+            .map(|name| Process::new(ProcessType::Resource, name.clone()))
+            .chain(
+                registry
+                    .plants
+                    .keys()
+                    .map(|name| Process::new(ProcessType::Plant, name.clone())),
+            )
+            .filter(yields)
+            .collect();
+        raw.sort_by(|a, b| {
+            (a.process_type == ProcessType::Plant, &a.name)
+                .cmp(&(b.process_type == ProcessType::Plant, &b.name))
+        });
+        let raw = raw.into_iter().next();
+        if alternatives {
+            return raw.into_iter().chain(recipes.iter().map(process)).collect();
+        }
+        match recipes.first() {
+            Some(recipe) if self.get_recipe_rule(recipe) == RecipeRule::Prefer => {
+                vec![process(recipe)]
+            }
+            recipe => raw.or_else(|| recipe.map(process)).into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::set_test_registry;
+    use crate::model::recipe_rules::RecipeRule;
+    use crate::model::Model;
+    use std::collections::HashMap;
+
+    #[test]
+    fn builds_from_outputs() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "category": "smelting",
+                        "energy_required": 3.2,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    },
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    },
+                    "cast-gear-wheel": {
+                        "name": "cast-gear-wheel",
+                        "category": "smelting",
+                        "energy_required": 5,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 3}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                },
+                "resource": {
+                    "iron-ore": {
+                        "name": "iron-ore",
+                        "minable": {"mining_time": 1, "result": "iron-ore"}
+                    }
+                },
+                "furnace": {
+                    "stone-furnace": {
+                        "name": "stone-furnace",
+                        "energy_usage": "90kW",
+                        "crafting_speed": 1,
+                        "crafting_categories": ["smelting"],
+                        "energy_source": {"type": "void"}
+                    },
+                    "steel-furnace": {
+                        "name": "steel-furnace",
+                        "energy_usage": "90kW",
+                        "crafting_speed": 2,
+                        "crafting_categories": ["smelting"],
+                        "energy_source": {"type": "void"}
+                    }
+                },
+                "mining-drill": {
+                    "mining-drill": {
+                        "name": "mining-drill",
+                        "energy_usage": "90kW",
+                        "mining_speed": 0.5,
+                        "resource_categories": ["basic-solid"],
+                        "energy_source": {"type": "void"}
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let build = |model: Model| {
+            let mut model = Model {
+                outputs: HashMap::from([
+                    (item("iron-gear-wheel"), 1.0),
+                    (item("copper-plate"), 1.0),
+                ]),
+                ..model
+            };
+            let unmade = model.auto_build(false);
+            assert_eq!(unmade, vec![item("copper-plate")]);
+            model
+                .processes
+                .into_iter()
+                .map(|process| (process.name, process.machine))
+                .collect::<Vec<(String, Option<String>)>>()
+        };
+        assert_eq!(
+            build(Model::default()),
+            [
+                ("iron-gear-wheel".into(), None),
+                ("iron-plate".into(), Some("steel-furnace".into())),
+                ("iron-ore".into(), Some("mining-drill".into())),
+            ]
+        );
+        let cast = [
+            ("cast-gear-wheel".into(), Some("steel-furnace".into())),
+            ("iron-ore".into(), Some("mining-drill".into())),
+        ];
+        assert_eq!(
+            build(Model {
+                preferred_recipes: HashMap::from([(
+                    item("iron-gear-wheel"),
+                    "cast-gear-wheel".into()
+                )]),
+                ..Default::default()
+            }),
+            cast
+        );
+        assert_eq!(
+            build(Model {
+                recipe_rules: HashMap::from([("iron-gear-wheel".into(), RecipeRule::Forbid)]),
+                ..Default::default()
+            }),
+            cast
+        );
+    }
+}
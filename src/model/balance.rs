@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+/// How the production and consumption of a material must match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaterialBalance {
+    /// More may be made than used, the surplus going to a sink or a void process.
+    #[default]
+    Overflow,
+    /// Exactly as much must be made as is used.
+    Balanced,
+    /// What isn't made may be bought in, without limit.
+    BuyIn,
+}
+
+pub const MATERIAL_BALANCES: [MaterialBalance; 3] = [
+    MaterialBalance::Overflow,
+    MaterialBalance::Balanced,
+    MaterialBalance::BuyIn,
+];
+
+impl From<&MaterialBalance> for String {
+    fn from(balance: &MaterialBalance) -> Self {
+        match balance {
+            MaterialBalance::Overflow => "May overflow",
+            MaterialBalance::Balanced => "Must balance",
+            MaterialBalance::BuyIn => "May be bought in",
+        }
+        .into()
+    }
+}
+
+impl TryFrom<&String> for MaterialBalance {
+    type Error = &'static str;
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        MATERIAL_BALANCES
+            .into_iter()
+            .find(|balance| String::from(balance) == *value)
+            .ok_or("Unknown material balance")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::balance::MaterialBalance;
+    use crate::model::objective::Objective;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn balances_materials() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 2}],
+                        "results": [
+                            {"type": "item", "name": "iron-plate", "amount": 1},
+                            {"type": "item", "name": "stone", "amount": 1}
+                        ]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            processes: vec![Process::new(ProcessType::Recipe, "iron-plate".into())],
+            outputs: HashMap::from([(item("iron-plate"), 1.0)]),
+            balances: HashMap::from([
+                (item("iron-ore"), MaterialBalance::BuyIn),
+                (item("stone"), MaterialBalance::Balanced),
+            ]),
+            ..Default::default()
+        };
+        assert!(matches!(model.solve(false), ModelResult::NoSolution(_)));
+
+        model
+            .processes
+            .push(Process::new(ProcessType::Void, item("stone").to_id()));
+        let ModelResult::OneSolution(solution) = model.solve(false) else {
+            panic!("the model should have a single solution");
+        };
+        let void = solution
+            .processes
+            .iter()
+            .find(|process| process.process.process_type == ProcessType::Void)
+            .unwrap();
+        assert!((void.crafts - 1.0).abs() < 1e-6);
+        let imbalances = solution.get_imbalances(&model.outputs);
+        let [(material, amount)] = imbalances.as_slice() else {
+            panic!("{:?}", imbalances);
+        };
+        assert_eq!(*material, item("iron-ore"));
+        assert!((amount + 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn buys_in_what_cannot_be_made() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "energy_required": 2000,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            processes: vec![Process::new(ProcessType::Recipe, "iron-plate".into())],
+            inputs: HashMap::from([(item("iron-ore"), 10.0)]),
+            outputs: HashMap::from([(item("iron-plate"), 1.0)]),
+            balances: HashMap::from([(item("iron-plate"), MaterialBalance::BuyIn)]),
+            ..Default::default()
+        };
+        let ModelResult::MultipleSolutions { lower_bounds, .. } = model.solve(false) else {
+            panic!("the ore input should be free to grow");
+        };
+        assert!((lower_bounds[&item("iron-ore")] - 1.0).abs() < 1e-6);
+
+        model.inputs.clear();
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        assert!((solution.processes[0].crafts - 1.0).abs() < 1e-6);
+
+        // a plate takes 2000 machines, yet is made rather than bought
+        model.objective = Objective::Machines;
+        model.inputs.clear();
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        assert!((solution.processes[0].crafts - 1.0).abs() < 1e-6);
+
+        model.processes.clear();
+        model.inputs.clear();
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("plates should be bought in");
+        };
+        assert!(solution.get_imbalances(&model.outputs) == [(item("iron-plate"), -1.0)]);
+    }
+}
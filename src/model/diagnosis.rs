@@ -0,0 +1,253 @@
+use crate::data::materials::MaterialPrototype;
+use crate::model::balance::MaterialBalance;
+use crate::model::time_unit::TimeUnit;
+use crate::model::{copy_variables, Model, Problem};
+use good_lp::{microlp, Expression, Solution, SolverModel};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// A reason a model has no solution, with the change that would fix it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Infeasibility {
+    /// An output no process makes.
+    UnproducedOutput(MaterialPrototype),
+    /// A material processes consume that nothing makes and that isn't an input.
+    MissingInput(MaterialPrototype),
+    /// An input limit lower than the model needs, by `missing` per second.
+    InputTooSmall {
+        material: MaterialPrototype,
+        missing: f64,
+    },
+    /// An output that can't be reached, short by `missing` per second.
+    OutputTooLarge {
+        material: MaterialPrototype,
+        missing: f64,
+    },
+    /// A material short by `missing` per second, that isn't an input or an output.
+    Shortage {
+        material: MaterialPrototype,
+        missing: f64,
+    },
+    /// A material that must balance but is made `surplus` per second too much.
+    Surplus {
+        material: MaterialPrototype,
+        surplus: f64,
+    },
+}
+
+impl Display for Infeasibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe(TimeUnit::Second))
+    }
+}
+
+impl Infeasibility {
+    /// Describes the reason, with rates in `time_unit`.
+    pub fn describe(&self, time_unit: TimeUnit) -> String {
+        match self {
+            Infeasibility::UnproducedOutput(material) => {
+                format!("No process makes the output {}", material.to_id())
+            }
+            Infeasibility::MissingInput(material) => format!(
+                "No process makes {}: add it as an input, or a process making it",
+                material.to_id()
+            ),
+            Infeasibility::InputTooSmall { material, missing } => format!(
+                "Raise the input {} by {}",
+                material.to_id(),
+                time_unit.format(*missing)
+            ),
+            Infeasibility::OutputTooLarge { material, missing } => format!(
+                "Lower the output {} by {}",
+                material.to_id(),
+                time_unit.format(*missing)
+            ),
+            Infeasibility::Shortage { material, missing } => format!(
+                "{} is short by {}",
+                material.to_id(),
+                time_unit.format(*missing)
+            ),
+            Infeasibility::Surplus { material, surplus } => format!(
+                "{} can't balance, {} is left over: let it overflow or void it",
+                material.to_id(),
+                time_unit.format(*surplus)
+            ),
+        }
+    }
+
+    pub fn get_material(&self) -> &MaterialPrototype {
+        match self {
+            Infeasibility::UnproducedOutput(material) | Infeasibility::MissingInput(material) => {
+                material
+            }
+            Infeasibility::InputTooSmall { material, .. }
+            | Infeasibility::OutputTooLarge { material, .. }
+            | Infeasibility::Shortage { material, .. }
+            | Infeasibility::Surplus { material, .. } => material,
+        }
+    }
+}
+
+impl Model {
+    /// Explains why the model has no solution. Missing producers are reported first, then the
+    /// smallest shortages and surpluses found by relaxing every material balance.
+    pub fn diagnose(&self, generate_inputs: bool, maximise_outputs: bool) -> Vec<Infeasibility> {
+        let surface = self.surface.as_deref();
+        let bonuses = self.get_productivity_bonuses();
+        let mut produced: HashSet<MaterialPrototype> = HashSet::new();
+        let mut consumed: HashSet<MaterialPrototype> = HashSet::new();
+        for process in self.processes.iter() {
+            for (material, amount) in process.get_net_amounts(surface, &bonuses) {
+                if amount > 0.0 {
+                    produced.insert(material);
+                } else if amount < 0.0 {
+                    consumed.insert(material);
+                }
+            }
+        }
+
+        let mut unproduced_outputs: Vec<&MaterialPrototype> = self
+            .outputs
+            .iter()
+            .filter(|&(material, &amount)| {
+                amount > 0.0
+                    && !maximise_outputs
+                    && !produced.contains(material)
+                    && !self.inputs.contains_key(material)
+            })
+            .map(|(material, _)| material)
+            .collect();
+        unproduced_outputs.sort_by_key(|material| material.to_id());
+        let mut missing_inputs: Vec<&MaterialPrototype> = consumed
+            .iter()
+            .filter(|material| {
+                !generate_inputs
+                    && !produced.contains(*material)
+                    && !self.inputs.contains_key(*material)
+                    && self.get_balance(material) != MaterialBalance::BuyIn
+            })
+            .collect();
+        missing_inputs.sort_by_key(|material| material.to_id());
+        let reported: HashSet<&MaterialPrototype> = unproduced_outputs
+            .iter()
+            .chain(missing_inputs.iter())
+            .copied()
+            .collect();
+        let mut infeasibilities: Vec<Infeasibility> = unproduced_outputs
+            .iter()
+            .map(|&material| Infeasibility::UnproducedOutput(material.clone()))
+            .chain(
+                missing_inputs
+                    .iter()
+                    .map(|&material| Infeasibility::MissingInput(material.clone())),
+            )
+            .collect();
+        let structural = infeasibilities.len();
+
+        let Problem {
+            variables,
+            constraints,
+            shortages,
+            surpluses,
+            ..
+        } = self.build_problem(generate_inputs, maximise_outputs, true);
+        let total = shortages
+            .values()
+            .chain(surpluses.values())
+            .fold(Expression::from(0), |acc, variable| acc + *variable);
+        if let Ok(results) = copy_variables(&variables)
+            .minimise(total)
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+        {
+            for (material, variable) in shortages.iter() {
+                let missing = results.value(*variable);
+                if missing <= 1e-9 || reported.contains(material) {
+                    continue;
+                }
+                let material = material.clone();
+                infeasibilities.push(if self.inputs.contains_key(&material) {
+                    Infeasibility::InputTooSmall { material, missing }
+                } else if self.outputs.contains_key(&material) {
+                    Infeasibility::OutputTooLarge { material, missing }
+                } else {
+                    Infeasibility::Shortage { material, missing }
+                });
+            }
+            for (material, variable) in surpluses.iter() {
+                let surplus = results.value(*variable);
+                if surplus > 1e-9 {
+                    infeasibilities.push(Infeasibility::Surplus {
+                        material: material.clone(),
+                        surplus,
+                    });
+                }
+            }
+        }
+        infeasibilities[structural..].sort_by_key(|infeasibility| infeasibility.to_string());
+        infeasibilities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::diagnosis::Infeasibility;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn diagnoses_infeasible_models() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            processes: vec![Process::new(ProcessType::Recipe, "iron-gear-wheel".into())],
+            outputs: HashMap::from([(item("iron-gear-wheel"), 2.0), (item("copper-cable"), 1.0)]),
+            ..Default::default()
+        };
+        let ModelResult::NoSolution(infeasibilities) = model.solve(false) else {
+            panic!("the model should have no solution");
+        };
+        let [unproduced, missing_input, Infeasibility::OutputTooLarge { material, missing }] =
+            infeasibilities.as_slice()
+        else {
+            panic!("{:?}", infeasibilities);
+        };
+        assert_eq!(
+            *unproduced,
+            Infeasibility::UnproducedOutput(item("copper-cable"))
+        );
+        assert_eq!(
+            *missing_input,
+            Infeasibility::MissingInput(item("iron-plate"))
+        );
+        // without plates, none of the gears can be made
+        assert_eq!(*material, item("iron-gear-wheel"));
+        assert!((missing - 2.0).abs() < 1e-6);
+
+        model.outputs.remove(&item("copper-cable"));
+        model.inputs.insert(item("iron-plate"), 3.0);
+        let ModelResult::NoSolution(infeasibilities) = model.solve(false) else {
+            panic!("the model should have no solution");
+        };
+        // half a gear is less to give up than a plate to add
+        let [Infeasibility::OutputTooLarge { material, missing }] = infeasibilities.as_slice()
+        else {
+            panic!("{:?}", infeasibilities);
+        };
+        assert_eq!(*material, item("iron-gear-wheel"));
+        assert!((missing - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::data::materials::MaterialPrototype;
+use crate::model::time_unit::TimeUnit;
+use crate::model::Model;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A limit on an amount, like the number of machines a process runs in or the rate of an
+/// output.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Limit {
+    Exact(f64),
+    Minimum(f64),
+    Maximum(f64),
+    /// The lowest and the highest amount.
+    Range(f64, f64),
+}
+
+/// The kinds of limit, each with a value of 0.
+pub const LIMIT_KINDS: [Limit; 4] = [
+    Limit::Exact(0.0),
+    Limit::Minimum(0.0),
+    Limit::Maximum(0.0),
+    Limit::Range(0.0, 0.0),
+];
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Limit::Range(lowest, highest) => {
+                write!(f, "{} {} and {}", self.get_kind(), lowest, highest)
+            }
+            _ => write!(f, "{} {}", self.get_kind(), self.get_value()),
+        }
+    }
+}
+
+impl TryFrom<&String> for Limit {
+    type Error = &'static str;
+    /// Returns the limit of the kind named `value`, with a value of 0.
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        LIMIT_KINDS
+            .into_iter()
+            .find(|limit| limit.get_kind() == value)
+            .ok_or("Unknown limit kind")
+    }
+}
+
+impl Limit {
+    pub fn get_kind(&self) -> &'static str {
+        match self {
+            Limit::Exact(_) => "Exactly",
+            Limit::Minimum(_) => "At least",
+            Limit::Maximum(_) => "At most",
+            Limit::Range(..) => "Between",
+        }
+    }
+
+    /// Returns the value of the limit, the lowest amount of a range.
+    pub fn get_value(&self) -> f64 {
+        match *self {
+            Limit::Exact(value)
+            | Limit::Minimum(value)
+            | Limit::Maximum(value)
+            | Limit::Range(value, _) => value,
+        }
+    }
+
+    /// Returns the limit of the same kind with `value`, the lowest amount of a range.
+    pub fn with_value(self, value: f64) -> Self {
+        match self {
+            Limit::Exact(_) => Limit::Exact(value),
+            Limit::Minimum(_) => Limit::Minimum(value),
+            Limit::Maximum(_) => Limit::Maximum(value),
+            Limit::Range(_, highest) => Limit::Range(value, highest.max(value)),
+        }
+    }
+
+    /// Returns the range with `highest` as its highest amount, no lower than its lowest.
+    /// Other limits are left as they are.
+    pub fn with_highest(self, highest: f64) -> Self {
+        match self {
+            Limit::Range(lowest, _) => Limit::Range(lowest, highest.max(lowest)),
+            limit => limit,
+        }
+    }
+
+    /// Describes the limit of a rate per second, in `time_unit`.
+    pub fn describe(&self, time_unit: TimeUnit) -> String {
+        match *self {
+            Limit::Range(lowest, highest) => format!(
+                "{} {} and {}",
+                self.get_kind(),
+                time_unit.convert(lowest),
+                time_unit.format(highest)
+            ),
+            _ => format!("{} {}", self.get_kind(), time_unit.format(self.get_value())),
+        }
+    }
+
+    /// Returns the lowest and the highest amount allowed, amounts never being negative.
+    pub fn get_bounds(&self) -> (f64, Option<f64>) {
+        match *self {
+            Limit::Exact(value) => (value, Some(value)),
+            Limit::Minimum(value) => (value, None),
+            Limit::Maximum(value) => (0.0, Some(value)),
+            Limit::Range(lowest, highest) => (lowest, Some(highest)),
+        }
+    }
+}
+
+impl Model {
+    /// Returns the limit of the output `material`, at least its amount by default.
+    pub fn get_output_limit(&self, material: &MaterialPrototype) -> Limit {
+        self.limits
+            .get(material)
+            .copied()
+            .unwrap_or_else(|| Limit::Minimum(self.outputs.get(material).copied().unwrap_or(0.0)))
+    }
+
+    /// Returns the limit of the input `material`, at most its amount by default.
+    pub fn get_input_limit(&self, material: &MaterialPrototype) -> Limit {
+        self.limits
+            .get(material)
+            .copied()
+            .unwrap_or_else(|| Limit::Maximum(self.inputs.get(material).copied().unwrap_or(0.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::limit::Limit;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn limits_machines() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                },
+                "assembling-machine": {
+                    "assembling-machine": {
+                        "name": "assembling-machine",
+                        "energy_usage": "100kW",
+                        "crafting_speed": 0.5,
+                        "crafting_categories": ["crafting"],
+                        "energy_source": {"type": "void"}
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let solve = |limit: Limit| {
+            let mut model = Model {
+                processes: vec![Process {
+                    machine: Some("assembling-machine".into()),
+                    machine_limit: Some(limit),
+                    ..Process::new(ProcessType::Recipe, "iron-gear-wheel".into())
+                }],
+                outputs: HashMap::from([(item("iron-gear-wheel"), 3.0)]),
+                ..Default::default()
+            };
+            model.solve(true)
+        };
+        let get_machines = |result: ModelResult| match result {
+            ModelResult::OneSolution(solution) => solution.processes[0].machines,
+            result => panic!("{:?}", result),
+        };
+        // each machine makes a gear per second
+        assert!((get_machines(solve(Limit::Minimum(4.0))) - 4.0).abs() < 1e-6);
+        assert!((get_machines(solve(Limit::Minimum(2.0))) - 3.0).abs() < 1e-6);
+        assert!(matches!(
+            solve(Limit::Maximum(2.0)),
+            ModelResult::NoSolution(_)
+        ));
+        // the output can grow to what the machines make
+        for (limit, most) in [(Limit::Exact(5.0), 5.0), (Limit::Maximum(12.0), 12.0)] {
+            let ModelResult::MultipleSolutions { higher_bounds, .. } = solve(limit) else {
+                panic!("the output should be free to grow");
+            };
+            assert!((higher_bounds[&item("iron-gear-wheel")] - most).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn limits_materials() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        // all 6 plates per second must be used, making 3 gears
+        let solve = |gears: Limit| {
+            let mut model = Model {
+                processes: vec![Process::new(ProcessType::Recipe, "iron-gear-wheel".into())],
+                inputs: HashMap::from([(item("iron-plate"), 6.0)]),
+                outputs: HashMap::from([(item("iron-gear-wheel"), gears.get_value())]),
+                limits: HashMap::from([
+                    (item("iron-plate"), Limit::Exact(6.0)),
+                    (item("iron-gear-wheel"), gears),
+                ]),
+                ..Default::default()
+            };
+            model.solve(false)
+        };
+        // a highest amount below the lowest is raised to it
+        assert_eq!(
+            Limit::Range(5.0, 8.0).with_highest(3.0),
+            Limit::Range(5.0, 5.0)
+        );
+        let ModelResult::OneSolution(solution) = solve(Limit::Range(1.0, 4.0)) else {
+            panic!("the model should have a single solution");
+        };
+        assert!((solution.processes[0].crafts - 3.0).abs() < 1e-6);
+        assert!(matches!(
+            solve(Limit::Range(1.0, 2.0)),
+            ModelResult::NoSolution(_)
+        ));
+        assert!(matches!(
+            solve(Limit::Maximum(2.0)),
+            ModelResult::NoSolution(_)
+        ));
+    }
+}
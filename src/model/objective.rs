@@ -0,0 +1,227 @@
+use crate::data::materials::MaterialPrototype;
+use crate::data::technologies::ProductivityBonuses;
+use crate::data::{Process, ProcessType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What `Model::solve` minimises among the solutions meeting the limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Objective {
+    /// The crafts per second of every process.
+    #[default]
+    Crafts,
+    /// The machines running the processes.
+    Machines,
+    /// The resources mined and the materials taken in, per second.
+    Resources,
+    /// The electricity the processes consume, in MW.
+    Power,
+    /// The emissions per minute of every pollutant.
+    Pollution,
+    /// The cost of the materials the processes consume, weighted by the model's costs. Inputs
+    /// are charged as they are consumed, and making a costed material earns nothing back.
+    Cost,
+}
+
+pub const OBJECTIVES: [Objective; 6] = [
+    Objective::Crafts,
+    Objective::Machines,
+    Objective::Resources,
+    Objective::Power,
+    Objective::Pollution,
+    Objective::Cost,
+];
+
+impl From<&Objective> for String {
+    fn from(objective: &Objective) -> Self {
+        match objective {
+            Objective::Crafts => "Crafts",
+            Objective::Machines => "Machines",
+            Objective::Resources => "Resources",
+            Objective::Power => "Power",
+            Objective::Pollution => "Pollution",
+            Objective::Cost => "Cost",
+        }
+        .into()
+    }
+}
+
+impl TryFrom<&String> for Objective {
+    type Error = &'static str;
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        OBJECTIVES
+            .into_iter()
+            .find(|objective| String::from(objective) == *value)
+            .ok_or("Unknown objective")
+    }
+}
+
+impl Objective {
+    /// Returns what running `process` once per second adds to the objective.
+    pub fn get_process_weight(
+        &self,
+        process: &Process,
+        surface: Option<&str>,
+        bonuses: &ProductivityBonuses,
+        costs: &HashMap<MaterialPrototype, f64>,
+    ) -> f64 {
+        match self {
+            Objective::Crafts => 1.0,
+            Objective::Machines => process.get_machine_count(1.0, surface),
+            Objective::Resources if process.process_type == ProcessType::Resource => process
+                .get_product_amounts(surface, bonuses)
+                .iter()
+                .map(|(_, amount)| amount)
+                .sum(),
+            Objective::Resources => 0.0,
+            Objective::Power => (-process.get_electricity(surface)).max(0.0),
+            Objective::Pollution => process.get_emissions(surface).values().sum::<f64>() * 60.0,
+            Objective::Cost => process
+                .get_net_amounts(surface, bonuses)
+                .iter()
+                .map(|(material, amount)| {
+                    (-amount).max(0.0) * costs.get(material).copied().unwrap_or(0.0)
+                })
+                .sum(),
+        }
+    }
+
+    /// Returns what taking in one unit of `material` per second adds to the objective.
+    pub fn get_input_weight(&self, material: &MaterialPrototype) -> f64 {
+        match (self, material) {
+            (Objective::Resources, MaterialPrototype::Item(..) | MaterialPrototype::Fluid(_)) => {
+                1.0
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::technologies::ProductivityBonuses;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::objective::Objective;
+    use crate::model::{Model, ModelResult};
+
+    #[test]
+    fn objective_picks_recipe() {
+        let _registry = set_test_registry(
+            r#"{
+                "item": {
+                    "iron-ore": {"name": "iron-ore", "stack_size": 50},
+                    "iron-plate": {"name": "iron-plate", "stack_size": 100}
+                },
+                "recipe": {
+                    "fast-plate": {
+                        "name": "fast-plate",
+                        "category": "smelting",
+                        "energy_required": 1,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    },
+                    "slow-plate": {
+                        "name": "slow-plate",
+                        "category": "smelting",
+                        "energy_required": 8,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    }
+                },
+                "furnace": {
+                    "furnace": {
+                        "name": "furnace",
+                        "energy_usage": "90kW",
+                        "crafting_speed": 1,
+                        "crafting_categories": ["smelting"],
+                        "energy_source": {"type": "void"}
+                    }
+                },
+                "resource": {
+                    "iron-ore": {
+                        "name": "iron-ore",
+                        "minable": {"mining_time": 1, "result": "iron-ore"}
+                    }
+                },
+                "mining-drill": {
+                    "mining-drill": {
+                        "name": "mining-drill",
+                        "energy_usage": "90kW",
+                        "mining_speed": 0.5,
+                        "resource_categories": ["basic-solid"],
+                        "energy_source": {"type": "void"}
+                    }
+                }
+            }"#,
+        );
+        let mut model = Model {
+            processes: ["fast-plate", "slow-plate"]
+                .map(|name| Process {
+                    machine: Some("furnace".into()),
+                    ..Process::new(ProcessType::Recipe, name.into())
+                })
+                .into(),
+            objective: Objective::Machines,
+            ..Model::default()
+        };
+        let plate = MaterialPrototype::Item("iron-plate".into(), "normal".into());
+        let ore = MaterialPrototype::Item("iron-ore".into(), "normal".into());
+        model.outputs.insert(plate, 1.0);
+
+        let crafts = |model: &mut Model, name: &str| {
+            model.inputs.clear();
+            let ModelResult::OneSolution(solution) = model.solve(true) else {
+                panic!("the model should have a single solution");
+            };
+            solution
+                .processes
+                .iter()
+                .find(|process| process.process.name == name)
+                .map_or(0.0, |process| process.crafts)
+        };
+        assert!((crafts(&mut model, "fast-plate") - 1.0).abs() < 1e-6);
+
+        model.objective = Objective::Cost;
+        model.costs.insert(ore.clone(), 1.0);
+        assert!((crafts(&mut model, "slow-plate") - 1.0).abs() < 1e-6);
+
+        // mining the costed ore earns nothing back, so it can't pay for itself
+        model.processes.push(Process {
+            machine: Some("mining-drill".into()),
+            ..Process::new(ProcessType::Resource, "iron-ore".into())
+        });
+        assert!((crafts(&mut model, "slow-plate") - 1.0).abs() < 1e-6);
+        assert!((crafts(&mut model, "iron-ore") - 1.0).abs() < 1e-6);
+
+        // a costed input is charged once, as the plates consume it
+        model.processes.pop();
+        model.inputs.clear();
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        let bonuses = ProductivityBonuses::default();
+        let cost = solution
+            .processes
+            .iter()
+            .map(|process| {
+                process.crafts
+                    * model.objective.get_process_weight(
+                        &process.process,
+                        None,
+                        &bonuses,
+                        &model.costs,
+                    )
+            })
+            .sum::<f64>()
+            + model
+                .inputs
+                .iter()
+                .map(|(material, amount)| amount * model.objective.get_input_weight(material))
+                .sum::<f64>();
+        assert!((model.inputs[&ore] - 1.0).abs() < 1e-6);
+        assert!((cost - 1.0).abs() < 1e-6, "{}", cost);
+
+        assert_eq!(Objective::default(), Objective::Crafts);
+    }
+}
@@ -0,0 +1,371 @@
+use crate::data::materials::{Material, MaterialPrototype};
+use crate::data::quality::get_quality_names;
+use crate::data::technologies::ProductivityBonuses;
+use crate::data::{get_registry, Process, ProcessType, Registry};
+use crate::model::solution::{ProcessSolution, Solution};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A recycle→reassemble cycle: the item made by `recipe` is recycled back into its
+/// ingredients until it reaches the target quality, and the recovered ingredients are
+/// assembled again at their own quality.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QualityLoop {
+    pub recipe: String,
+    pub recycling_recipe: String,
+    pub item: String,
+    /// The qualities the loop assembles at, from the quality of its inputs up to the target.
+    pub qualities: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct QualityLoopReport {
+    /// Target quality items made per second.
+    pub output: f64,
+    /// Target quality items made per unit of each ingredient fed into the loop.
+    pub output_per_input: Vec<(MaterialPrototype, f64)>,
+    pub assembler_machines: f64,
+    pub recycler_machines: f64,
+    /// The amount of each material produced and consumed per second inside the loop.
+    pub flows: Vec<(MaterialPrototype, f64, f64)>,
+}
+
+/// Returns the recycling recipe turning `item` back into its ingredients.
+pub fn find_recycling_recipe(registry: &Registry, item: &str) -> Option<String> {
+    let is_recycling = |name: &String| {
+        registry.recipes.get(name).is_some_and(|recipe| {
+            recipe.category == "recycling"
+                && matches!(recipe.ingredients.as_slice(),
+                    [Material::Item(ingredient)] if ingredient.name == item)
+        })
+    };
+    let name = format!("{}-recycling", item);
+    if is_recycling(&name) {
+        return Some(name);
+    }
+    let mut names: Vec<&String> = registry
+        .recipes
+        .keys()
+        .filter(|name| is_recycling(name))
+        .collect();
+    names.sort();
+    names.first().map(|name| (*name).clone())
+}
+
+impl QualityLoop {
+    /// Creates the loop upcycling the main product of the `process` recipe to `quality`.
+    pub fn new(process: &Process, quality: &str) -> Result<Self, String> {
+        let registry = get_registry();
+        if process.process_type != ProcessType::Recipe {
+            return Err("Only recipes can be recycled".into());
+        }
+        let item = registry
+            .recipes
+            .get(&process.name)
+            .unwrap()
+            .results
+            .iter()
+            .find_map(|result| match result {
+                Material::Item(item) => Some(item.name.clone()),
+                Material::Fluid(_) => None,
+            })
+            .ok_or("The recipe doesn't make any item")?;
+        let recycling_recipe = find_recycling_recipe(&registry, &item)
+            .ok_or(format!("There's no recycling recipe for {}", item))?;
+        let qualities = get_quality_names(&registry);
+        let (Some(start), Some(end)) = (
+            qualities.iter().position(|name| *name == process.quality),
+            qualities.iter().position(|name| name == quality),
+        ) else {
+            return Err(format!("Unknown quality {}", quality));
+        };
+        if start >= end {
+            return Err(format!("{} isn't above {}", quality, process.quality));
+        }
+        Ok(QualityLoop {
+            recipe: process.name.clone(),
+            recycling_recipe,
+            item,
+            qualities: qualities[start..=end].to_vec(),
+        })
+    }
+
+    pub fn get_target(&self) -> MaterialPrototype {
+        MaterialPrototype::Item(self.item.clone(), self.qualities.last().unwrap().clone())
+    }
+
+    /// Returns whether `process` is one of the assemblers of the loop.
+    pub fn is_assembler(&self, process: &Process) -> bool {
+        process.process_type == ProcessType::Recipe
+            && process.name == self.recipe
+            && self.qualities.contains(&process.quality)
+    }
+
+    /// Returns whether `process` is one of the recyclers of the loop.
+    pub fn is_recycler(&self, process: &Process) -> bool {
+        process.process_type == ProcessType::Recipe
+            && process.name == self.recycling_recipe
+            && self.qualities[..self.qualities.len() - 1].contains(&process.quality)
+    }
+
+    /// Returns the processes the loop needs that aren't in `processes` yet. Assemblers copy
+    /// the machine and modules of `process`, recyclers get the first machine able to run them.
+    pub fn get_missing_processes(&self, process: &Process, processes: &[Process]) -> Vec<Process> {
+        let registry = get_registry();
+        let recycling_category = &registry
+            .recipes
+            .get(&self.recycling_recipe)
+            .unwrap()
+            .category;
+        let recycler = registry
+            .crafting_machines
+            .values()
+            .filter(|machine| machine.crafting_categories.contains(recycling_category))
+            .map(|machine| machine.name.clone())
+            .min();
+        self.qualities
+            .iter()
+            .flat_map(|quality| {
+                let assembler = Process {
+                    quality: quality.clone(),
+                    ..process.clone()
+                };
+                let recycler = Process {
+                    quality: quality.clone(),
+                    machine: recycler.clone(),
+                    ..Process::new(ProcessType::Recipe, self.recycling_recipe.clone())
+                };
+                [
+                    Some(assembler),
+                    self.is_recycler(&recycler).then_some(recycler),
+                ]
+            })
+            .flatten()
+            .filter(|process| !processes.contains(process))
+            .collect()
+    }
+
+    /// Returns the item ingredients at the quality the loop is fed with. The loop recovers
+    /// some of them too, so they are generated as inputs even though they are produced.
+    pub fn get_input_materials(&self) -> Vec<MaterialPrototype> {
+        get_registry()
+            .recipes
+            .get(&self.recipe)
+            .unwrap()
+            .ingredients
+            .iter()
+            .filter_map(|ingredient| match ingredient {
+                Material::Item(ingredient) => Some(MaterialPrototype::Item(
+                    ingredient.name.clone(),
+                    self.qualities[0].clone(),
+                )),
+                Material::Fluid(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the materials the loop must use up: the item below the target quality, which
+    /// is all recycled, and the item ingredients recovered above the input quality, which are
+    /// all assembled again.
+    pub fn get_balanced_materials(&self) -> Vec<MaterialPrototype> {
+        let registry = get_registry();
+        let mut materials: Vec<MaterialPrototype> = self.qualities[..self.qualities.len() - 1]
+            .iter()
+            .map(|quality| MaterialPrototype::Item(self.item.clone(), quality.clone()))
+            .collect();
+        for ingredient in registry
+            .recipes
+            .get(&self.recipe)
+            .unwrap()
+            .ingredients
+            .iter()
+        {
+            if let Material::Item(ingredient) = ingredient {
+                materials.extend(self.qualities[1..].iter().map(|quality| {
+                    MaterialPrototype::Item(ingredient.name.clone(), quality.clone())
+                }));
+            }
+        }
+        materials
+    }
+
+    /// Summarises the steady state of the loop in `solution`.
+    pub fn get_report(
+        &self,
+        solution: &Solution,
+        surface: Option<&str>,
+        bonuses: &ProductivityBonuses,
+    ) -> QualityLoopReport {
+        let mut report = QualityLoopReport::default();
+        let mut flows: HashMap<MaterialPrototype, (f64, f64)> = HashMap::new();
+        for ProcessSolution {
+            process,
+            crafts,
+            machines,
+            ..
+        } in solution.processes.iter()
+        {
+            let is_assembler = self.is_assembler(process);
+            if !is_assembler && !self.is_recycler(process) {
+                continue;
+            }
+            if is_assembler {
+                report.assembler_machines += machines;
+            } else {
+                report.recycler_machines += machines;
+            }
+            for ingredient in process.get_ingredients() {
+                flows.entry(ingredient.get_prototype()).or_default().1 +=
+                    ingredient.get_average_amount(0.0) * crafts;
+            }
+            for (product, amount) in process.get_product_amounts(surface, bonuses) {
+                flows.entry(product).or_default().0 += amount * crafts;
+            }
+        }
+        let target = self.get_target();
+        report.output = flows.get(&target).map_or(0.0, |(produced, _)| *produced);
+        report.output_per_input = flows
+            .iter()
+            .filter(|(_, (produced, consumed))| consumed > produced)
+            .filter(|(material, _)| match material {
+                MaterialPrototype::Item(_, quality) => *quality == self.qualities[0],
+                MaterialPrototype::Fluid(_) => true,
+                MaterialPrototype::Electricity | MaterialPrototype::Research(_) => false,
+            })
+            .map(|(material, (produced, consumed))| {
+                (material.clone(), report.output / (consumed - produced))
+            })
+            .collect();
+        report
+            .output_per_input
+            .sort_by_key(|(material, _)| material.to_id());
+        report.flows = flows
+            .into_iter()
+            .map(|(material, (produced, consumed))| (material, produced, consumed))
+            .collect();
+        report
+            .flows
+            .sort_by_key(|(material, _, _)| material.to_id());
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::technologies::ProductivityBonuses;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::{Model, ModelResult};
+
+    #[test]
+    fn solves_quality_loop() {
+        let _registry = set_test_registry(
+            r#"{
+                "quality": {
+                    "normal": {"name": "normal", "level": 0, "next": "uncommon", "next_probability": 0.1},
+                    "uncommon": {"name": "uncommon", "level": 1, "next": "rare", "next_probability": 0.1},
+                    "rare": {"name": "rare", "level": 2}
+                },
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    },
+                    "iron-gear-wheel-recycling": {
+                        "name": "iron-gear-wheel-recycling",
+                        "category": "recycling",
+                        "ingredients": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1, "probability": 0.25}]
+                    }
+                },
+                "assembling-machine": {
+                    "assembling-machine": {
+                        "name": "assembling-machine",
+                        "energy_usage": "75kW",
+                        "crafting_speed": 1,
+                        "crafting_categories": ["crafting"],
+                        "energy_source": {"type": "electric"},
+                        "allowed_effects": ["quality"],
+                        "allowed_module_categories": ["quality"],
+                        "module_slots": 1
+                    },
+                    "recycler": {
+                        "name": "recycler",
+                        "energy_usage": "180kW",
+                        "crafting_speed": 0.5,
+                        "crafting_categories": ["recycling"],
+                        "energy_source": {"type": "electric"}
+                    }
+                },
+                "module": {
+                    "quality-module": {
+                        "name": "quality-module",
+                        "stack_size": 50,
+                        "category": "quality",
+                        "effect": {"quality": 1.0}
+                    }
+                }
+            }"#,
+        );
+
+        let process = Process {
+            machine: Some("assembling-machine".into()),
+            modules: vec!["quality-module".into()],
+            ..Process::new(ProcessType::Recipe, "iron-gear-wheel".into())
+        };
+        let mut model = Model::default();
+        model.add_quality_loop(&process, "rare").unwrap();
+        assert_eq!(model.processes.len(), 5);
+        let target = MaterialPrototype::Item("iron-gear-wheel".into(), "rare".into());
+        model.outputs.insert(target, 1.0);
+
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the loop should have a single solution");
+        };
+        let report =
+            model.quality_loops[0].get_report(&solution, None, &ProductivityBonuses::default());
+        assert!((report.output - 1.0).abs() < 1e-6);
+        assert!(report.assembler_machines > 0.0);
+        assert!(report.recycler_machines > 0.0);
+        let [(input, per_input)] = report.output_per_input.as_slice() else {
+            panic!("the loop should have a single input");
+        };
+        assert_eq!(
+            *input,
+            MaterialPrototype::Item("iron-plate".into(), "normal".into())
+        );
+        // With a chance of 10%, normal plates make 90% normal, 9% uncommon and 1% rare gears,
+        // and uncommon plates 90% uncommon and 10% rare gears. Recycling gives back a quarter
+        // of the plates, so the uncommon assembler takes 0.0225 / 0.775 of the crafts of the
+        // normal one, which makes 0.01 / 0.775 rare gears per craft: 77.5 crafts for one rare
+        // gear, using 0.775 normal plates each after recycling.
+        assert!(
+            (per_input - 1.0 / (77.5 * 0.775)).abs() < 1e-9,
+            "{}",
+            per_input
+        );
+        let assemblers: Vec<f64> = solution
+            .processes
+            .iter()
+            .filter(|process| model.quality_loops[0].is_assembler(&process.process))
+            .map(|process| process.crafts)
+            .collect();
+        assert!((assemblers[0] - 77.5).abs() < 1e-6, "{:?}", assemblers);
+        assert!((assemblers[1] - 2.25).abs() < 1e-6, "{:?}", assemblers);
+        // everything below the target quality is recycled, and everything recovered above
+        // the input quality is assembled again
+        for (material, produced, consumed) in report.flows.iter() {
+            let MaterialPrototype::Item(name, quality) = material else {
+                continue;
+            };
+            let balanced = match name.as_str() {
+                "iron-gear-wheel" => quality != "rare",
+                _ => quality != "normal",
+            };
+            if balanced {
+                assert!((produced - consumed).abs() < 1e-6, "{:?}", material);
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::data::materials::MaterialPrototype;
+use good_lp::{Constraint, Expression};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Materials whose net amounts are kept in proportion, like science packs at 1:1:1.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ratio {
+    /// Each material with its share.
+    pub materials: Vec<(MaterialPrototype, f64)>,
+}
+
+impl Ratio {
+    /// Describes the ratio, e.g. `item:automation-science-pack : item:logistic-science-pack = 1 : 1`.
+    pub fn get_label(&self) -> String {
+        let (materials, shares): (Vec<String>, Vec<String>) = self
+            .materials
+            .iter()
+            .map(|(material, share)| (material.to_id(), share.to_string()))
+            .unzip();
+        format!("{} = {}", materials.join(" : "), shares.join(" : "))
+    }
+
+    /// Returns the constraints keeping the net amounts made of the materials, given by
+    /// `amounts`, in proportion. Each material is held against the first, and materials no
+    /// process makes or uses count as 0.
+    pub fn get_constraints(
+        &self,
+        amounts: &HashMap<MaterialPrototype, Expression>,
+    ) -> Vec<Box<dyn Fn() -> Constraint>> {
+        let get_amount = |material: &MaterialPrototype| {
+            amounts
+                .get(material)
+                .cloned()
+                .unwrap_or_else(|| Expression::from(0))
+        };
+        let Some((first, first_share)) = self.materials.first() else {
+            return vec![];
+        };
+        let first_amount = get_amount(first);
+        self.materials
+            .iter()
+            .skip(1)
+            .map(|(material, share)| {
+                let left = first_amount.clone() * *share;
+                let right = get_amount(material) * *first_share;
+                Box::new(move || left.clone().eq(right.clone())) as Box<dyn Fn() -> Constraint>
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::ratio::Ratio;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn keeps_ratios() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "automation-science-pack": {
+                        "name": "automation-science-pack",
+                        "energy_required": 5,
+                        "ingredients": [{"type": "item", "name": "copper-plate", "amount": 1}],
+                        "results": [{"type": "item", "name": "automation-science-pack", "amount": 1}]
+                    },
+                    "logistic-science-pack": {
+                        "name": "logistic-science-pack",
+                        "energy_required": 6,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 1}],
+                        "results": [{"type": "item", "name": "logistic-science-pack", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            processes: ["automation-science-pack", "logistic-science-pack"]
+                .map(|name| Process::new(ProcessType::Recipe, name.into()))
+                .into(),
+            outputs: HashMap::from([
+                (item("automation-science-pack"), 2.0),
+                (item("logistic-science-pack"), 0.0),
+            ]),
+            ratios: vec![Ratio {
+                materials: vec![
+                    (item("automation-science-pack"), 1.0),
+                    (item("logistic-science-pack"), 2.0),
+                ],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            model.ratios[0].get_label(),
+            "item:automation-science-pack : item:logistic-science-pack = 1 : 2"
+        );
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        let crafts: Vec<f64> = solution
+            .processes
+            .iter()
+            .map(|process| process.crafts)
+            .collect();
+        assert!((crafts[0] - 2.0).abs() < 1e-6, "{:?}", crafts);
+        assert!((crafts[1] - 4.0).abs() < 1e-6, "{:?}", crafts);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::data::get_registry;
+use crate::model::Model;
+use serde::{Deserialize, Serialize};
+
+/// Whether a recipe, or the recipes of a category, may be used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecipeRule {
+    #[default]
+    Allow,
+    /// Picked over the other recipes making the same material.
+    Prefer,
+    /// Left out of the menus and auto-build, and never run by the solver.
+    Forbid,
+}
+
+pub const RECIPE_RULES: [RecipeRule; 3] =
+    [RecipeRule::Allow, RecipeRule::Prefer, RecipeRule::Forbid];
+
+impl From<&RecipeRule> for String {
+    fn from(rule: &RecipeRule) -> Self {
+        match rule {
+            RecipeRule::Allow => "Allowed",
+            RecipeRule::Prefer => "Preferred",
+            RecipeRule::Forbid => "Forbidden",
+        }
+        .into()
+    }
+}
+
+impl TryFrom<&String> for RecipeRule {
+    type Error = &'static str;
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        RECIPE_RULES
+            .into_iter()
+            .find(|rule| String::from(rule) == *value)
+            .ok_or("Unknown recipe rule")
+    }
+}
+
+impl Model {
+    /// Returns the rule of `recipe`: its own rule, else the rule of its category.
+    pub fn get_recipe_rule(&self, recipe: &str) -> RecipeRule {
+        self.recipe_rules.get(recipe).copied().unwrap_or_else(|| {
+            get_registry()
+                .recipes
+                .get(recipe)
+                .and_then(|recipe| self.category_rules.get(&recipe.category))
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::set_test_registry;
+    use crate::model::recipe_rules::RecipeRule;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn applies_recipe_rules() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "category": "smelting",
+                        "energy_required": 3.2,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    },
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    },
+                    "cast-gear-wheel": {
+                        "name": "cast-gear-wheel",
+                        "category": "smelting",
+                        "energy_required": 5,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 3}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            outputs: HashMap::from([(item("iron-gear-wheel"), 1.0)]),
+            ..Default::default()
+        };
+        assert_eq!(model.auto_build(true), vec![item("iron-ore")]);
+        let names: Vec<&str> = model
+            .processes
+            .iter()
+            .map(|process| process.name.as_str())
+            .collect();
+        assert_eq!(names, ["iron-gear-wheel", "cast-gear-wheel", "iron-plate"]);
+
+        let get_crafts = |model: &mut Model| {
+            let ModelResult::OneSolution(solution) = model.solve(true) else {
+                panic!("the model should have a single solution");
+            };
+            solution
+                .processes
+                .iter()
+                .map(|process| process.crafts)
+                .collect::<Vec<f64>>()
+        };
+        // casting takes 5 seconds of crafting per gear, against 6.9 from plates
+        let crafts = get_crafts(&mut model);
+        assert!((crafts[1] - 1.0).abs() < 1e-6, "{:?}", crafts);
+
+        model
+            .category_rules
+            .insert("smelting".into(), RecipeRule::Forbid);
+        model
+            .recipe_rules
+            .insert("iron-plate".into(), RecipeRule::Allow);
+        assert_eq!(model.get_recipe_rule("cast-gear-wheel"), RecipeRule::Forbid);
+        assert_eq!(model.get_recipe_rule("iron-plate"), RecipeRule::Allow);
+        let crafts = get_crafts(&mut model);
+        assert!(crafts[1].abs() < 1e-9, "{:?}", crafts);
+        assert!((crafts[0] - 1.0).abs() < 1e-6, "{:?}", crafts);
+    }
+}
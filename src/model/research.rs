@@ -0,0 +1,127 @@
+use crate::data::materials::MaterialPrototype;
+use crate::data::{get_registry, ProcessType};
+use crate::model::solution::Solution;
+use std::collections::HashMap;
+use tap::Tap;
+
+/// The research a solution does on one technology.
+#[derive(Debug, Default)]
+pub struct ResearchReport {
+    pub technology: String,
+    /// Research units finished per second, lab productivity included.
+    pub units_per_second: f64,
+    /// The science packs consumed per second.
+    pub packs_per_second: Vec<(MaterialPrototype, f64)>,
+    pub labs: f64,
+    /// The time the whole technology takes, in seconds, or `None` when its unit count is a
+    /// formula.
+    pub time: Option<f64>,
+}
+
+/// Summarises the research done by the labs of `solution`, sorted by technology.
+pub fn get_research_reports(solution: &Solution) -> Vec<ResearchReport> {
+    let mut reports: HashMap<String, (ResearchReport, HashMap<MaterialPrototype, f64>)> =
+        HashMap::new();
+    for process in solution.processes.iter() {
+        if process.process.process_type != ProcessType::Research {
+            continue;
+        }
+        let (report, packs) = reports.entry(process.process.name.clone()).or_default();
+        report.labs += process.machines;
+        for (material, &amount) in process.amounts.iter() {
+            match material {
+                MaterialPrototype::Research(_) => report.units_per_second += amount,
+                MaterialPrototype::Item(..) => {
+                    *packs.entry(material.clone()).or_default() -= amount;
+                }
+                MaterialPrototype::Fluid(_) | MaterialPrototype::Electricity => {}
+            }
+        }
+    }
+    let registry = get_registry();
+    let mut reports: Vec<ResearchReport> = reports
+        .into_iter()
+        .map(|(technology, (report, packs))| {
+            let count = registry
+                .technologies
+                .get(&technology)
+                .and_then(|technology| technology.unit.as_ref())
+                .and_then(|unit| unit.count);
+            ResearchReport {
+                time: count.map(|count| count / report.units_per_second),
+                packs_per_second: packs
+                    .into_iter()
+                    .collect::<Vec<_>>()
+                    .tap_mut(|packs| packs.sort_by_key(|(pack, _)| pack.to_id())),
+                technology,
+                ..report
+            }
+        })
+        .collect();
+    reports.sort_by(|a, b| a.technology.cmp(&b.technology));
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::research::get_research_reports;
+    use crate::model::{Model, ModelResult};
+
+    #[test]
+    fn plans_research() {
+        let _registry = set_test_registry(
+            r#"{
+                "item": {
+                    "automation-science-pack": {"name": "automation-science-pack", "stack_size": 200},
+                    "agricultural-science-pack": {"name": "agricultural-science-pack", "stack_size": 200}
+                },
+                "technology": {
+                    "automation": {
+                        "name": "automation",
+                        "unit": {
+                            "count": 100,
+                            "time": 10,
+                            "ingredients": [
+                                ["automation-science-pack", 1],
+                                ["agricultural-science-pack", 1]
+                            ]
+                        }
+                    }
+                },
+                "lab": {
+                    "biolab": {
+                        "name": "biolab",
+                        "energy_usage": "600kW",
+                        "researching_speed": 2,
+                        "inputs": ["automation-science-pack", "agricultural-science-pack"],
+                        "energy_source": {"type": "void"},
+                        "science_pack_drain_rate_percent": 50
+                    }
+                }
+            }"#,
+        );
+        let mut model = Model::default();
+        model.processes.push(Process {
+            machine: Some("biolab".into()),
+            ..Process::new(ProcessType::Research, "automation".into())
+        });
+        model
+            .outputs
+            .insert(MaterialPrototype::Research("automation".into()), 0.5);
+
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the research should have a single solution");
+        };
+        let [report] = get_research_reports(&solution).try_into().unwrap();
+        assert_eq!(report.technology, "automation");
+        assert!((report.units_per_second - 0.5).abs() < 1e-6);
+        assert!((report.labs - 2.5).abs() < 1e-6);
+        assert!((report.time.unwrap() - 200.0).abs() < 1e-6);
+        assert_eq!(report.packs_per_second.len(), 2);
+        for (_, amount) in report.packs_per_second {
+            assert!((amount - 0.25).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::data::materials::MaterialPrototype;
+use crate::data::Process;
+use crate::model::solution::Solution;
+use crate::model::{buy_least, copy_variables, Model, ModelResult, Problem};
+use good_lp::{
+    microlp, Constraint, IntoAffineExpression, ResolutionError, Solution as _, SolverModel,
+};
+use std::collections::HashSet;
+
+/// The step by which limits are moved to measure how the objective follows them.
+const STEP: f64 = 1e-3;
+
+/// How an input or output of a solved model weighs on its objective.
+#[derive(Clone, Debug)]
+pub struct MaterialSensitivity {
+    pub material: MaterialPrototype,
+    /// How much one more unit per second of the material, given for free, lowers the objective.
+    pub shadow_price: f64,
+    /// Whether the solution is held back by the input limit or output target of the material.
+    pub binding: bool,
+}
+
+/// How the objective of a solved model would change with its limits.
+#[derive(Clone, Debug, Default)]
+pub struct Sensitivity {
+    /// The inputs and outputs, sorted by material.
+    pub materials: Vec<MaterialSensitivity>,
+    /// How much running each unused process once per second would add to the objective.
+    pub reduced_costs: Vec<(Process, f64)>,
+}
+
+impl Model {
+    /// Solves the model and measures the sensitivity of its optimal solution. Unlike
+    /// `Model::solve`, inputs and outputs that could move without changing the objective
+    /// still give that one solution.
+    pub fn solve_with_sensitivity(&self, generate_inputs: bool) -> ModelResult {
+        let Problem {
+            variables,
+            processes,
+            mut constraints,
+            bought_variables,
+            objective,
+            ..
+        } = self.build_problem(generate_inputs, false, false);
+        if buy_least(&variables, &mut constraints, &bought_variables).is_err() {
+            return ModelResult::NoSolution(self.diagnose(generate_inputs, false));
+        }
+        let results = match copy_variables(&variables)
+            .minimise(objective)
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+        {
+            Ok(results) => results,
+            Err(ResolutionError::Unbounded) => return ModelResult::Unbounded,
+            Err(_) => return ModelResult::NoSolution(self.diagnose(generate_inputs, false)),
+        };
+        ModelResult::OneSolution(Solution {
+            sensitivity: Some(self.get_sensitivity(generate_inputs)),
+            ..Solution::new(
+                self.processes
+                    .iter()
+                    .zip(processes)
+                    .map(|(process, variable)| (process.clone(), results.value(variable))),
+                self.surface.as_deref(),
+                &self.get_productivity_bonuses(),
+            )
+        })
+    }
+
+    /// Measures the shadow price of each input and output, and the reduced cost of each
+    /// unused process, by solving the model again with the limit moved a little. The solver
+    /// doesn't give dual values.
+    pub fn get_sensitivity(&self, generate_inputs: bool) -> Sensitivity {
+        let Problem {
+            variables,
+            processes,
+            constraints,
+            input_variables,
+            output_variables,
+            shortages,
+            surpluses,
+            objective,
+            ..
+        } = self.build_problem(generate_inputs, false, true);
+        // Solves the model with `STEP` of `supplied` given for free, or with the process at
+        // `forced` run at least `STEP` times per second.
+        let solve = |supplied: Option<&MaterialPrototype>, forced: Option<usize>| {
+            let mut extra: Vec<Constraint> = shortages
+                .iter()
+                .map(|(material, &shortage)| {
+                    let amount = if Some(material) == supplied {
+                        STEP
+                    } else {
+                        0.0
+                    };
+                    shortage.into_expression().eq(amount)
+                })
+                .chain(
+                    surpluses
+                        .values()
+                        .map(|&surplus| surplus.into_expression().eq(0.0)),
+                )
+                .collect();
+            if let Some(index) = forced {
+                extra.push(processes[index].into_expression().geq(STEP));
+            }
+            copy_variables(&variables)
+                .minimise(objective.clone())
+                .using(microlp)
+                .with_all(constraints.iter().map(|function| function()))
+                .with_all(extra)
+                .solve()
+                .ok()
+        };
+
+        let Some(results) = solve(None, None) else {
+            return Sensitivity::default();
+        };
+        let base = results.eval(objective.clone());
+        // Inputs are binding when used up to their limit, outputs when made no more than needed.
+        let binding: HashSet<&MaterialPrototype> = input_variables
+            .iter()
+            .filter(|&(material, &variable)| {
+                self.inputs
+                    .get(material)
+                    .is_some_and(|&limit| results.value(variable) >= limit - 1e-6)
+            })
+            .chain(output_variables.iter().filter(|&(material, &variable)| {
+                results.value(variable) <= self.outputs[material] + 1e-6
+            }))
+            .map(|(material, _)| material)
+            .collect();
+        let mut materials: Vec<&MaterialPrototype> = input_variables
+            .keys()
+            .chain(output_variables.keys())
+            .collect::<HashSet<&MaterialPrototype>>()
+            .into_iter()
+            .collect();
+        materials.sort_by_key(|material| material.to_id());
+        Sensitivity {
+            materials: materials
+                .iter()
+                .map(|material| MaterialSensitivity {
+                    shadow_price: shortages
+                        .get(material)
+                        .and_then(|_| solve(Some(material), None))
+                        .map_or(0.0, |results| {
+                            (base - results.eval(objective.clone())) / STEP
+                        }),
+                    binding: binding.contains(material),
+                    material: (*material).clone(),
+                })
+                .collect(),
+            reduced_costs: self
+                .processes
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| results.value(processes[index]) <= 1e-9)
+                .filter_map(|(index, process)| {
+                    let results = solve(None, Some(index))?;
+                    Some((
+                        process.clone(),
+                        (results.eval(objective.clone()) - base) / STEP,
+                    ))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::objective::Objective;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn prices_limits() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "energy_required": 3.2,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    },
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    },
+                    "slow-gear-wheel": {
+                        "name": "slow-gear-wheel",
+                        "energy_required": 2,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            processes: ["iron-plate", "iron-gear-wheel", "slow-gear-wheel"]
+                .map(|name| Process::new(ProcessType::Recipe, name.into()))
+                .into(),
+            outputs: HashMap::from([(item("iron-gear-wheel"), 2.0)]),
+            objective: Objective::Machines,
+            ..Default::default()
+        };
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        assert!(solution.sensitivity.is_none());
+        let sensitivity = model.get_sensitivity(true);
+        let [gear, ore] = sensitivity.materials.as_slice() else {
+            panic!("{:?}", sensitivity.materials);
+        };
+        assert_eq!(gear.material, item("iron-gear-wheel"));
+        assert!(gear.binding);
+        // a gear takes half a second of crafting and two plates of 3.2 seconds each
+        assert!(
+            (gear.shadow_price - 6.9).abs() < 1e-6,
+            "{}",
+            gear.shadow_price
+        );
+        assert_eq!(ore.material, item("iron-ore"));
+        // the generated input is exactly what the model needs
+        assert!(ore.binding);
+        assert!(ore.shadow_price.abs() < 1e-6);
+        let [(process, reduced_cost)] = sensitivity.reduced_costs.as_slice() else {
+            panic!("{:?}", sensitivity.reduced_costs);
+        };
+        assert_eq!(process.name, "slow-gear-wheel");
+        assert!((reduced_cost - 1.5).abs() < 1e-6, "{}", reduced_cost);
+
+        // ore to spare gives several solutions, yet the optimal one is still measured
+        model.inputs = HashMap::from([(item("iron-ore"), 10.0)]);
+        assert!(matches!(
+            model.solve(false),
+            ModelResult::MultipleSolutions { .. }
+        ));
+        let ModelResult::OneSolution(solution) = model.solve_with_sensitivity(false) else {
+            panic!("the optimal solution should be measured");
+        };
+        assert!((solution.processes[0].crafts - 4.0).abs() < 1e-6);
+        let Some(sensitivity) = solution.sensitivity else {
+            panic!("the solution should have its sensitivity");
+        };
+        let [gear, ore] = sensitivity.materials.as_slice() else {
+            panic!("{:?}", sensitivity.materials);
+        };
+        assert!(gear.binding);
+        assert!((gear.shadow_price - 6.9).abs() < 1e-6);
+        // only 4 of the 10 ores are used
+        assert!(!ore.binding);
+        assert!(ore.shadow_price.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::data::materials::MaterialPrototype;
+use crate::data::technologies::ProductivityBonuses;
+use crate::data::Process;
+use crate::model::sensitivity::Sensitivity;
+use std::collections::HashMap;
+
+/// What one process does in a solution. Rates are per second.
+#[derive(Clone, Debug)]
+pub struct ProcessSolution {
+    pub process: Process,
+    pub crafts: f64,
+    pub machines: f64,
+    /// The speed of the machine, speed effects included.
+    pub speed: f64,
+    /// The productivity bonus, research included.
+    pub productivity: f64,
+    /// The consumption effect on the energy the machines use while working.
+    pub consumption: f64,
+    /// The electricity made, in MW, negative when the process uses it.
+    pub power: f64,
+    /// The emissions of each pollutant per minute.
+    pub emissions: HashMap<String, f64>,
+    /// The net amount of each material made, negative when consumed.
+    pub amounts: HashMap<MaterialPrototype, f64>,
+}
+
+/// How much of a material the processes of a solution make and use, per second.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaterialRates {
+    pub produced: f64,
+    pub consumed: f64,
+}
+
+impl MaterialRates {
+    pub fn get_net(&self) -> f64 {
+        self.produced - self.consumed
+    }
+}
+
+/// A solved model: what each process does, and what flows through the factory.
+#[derive(Clone, Debug, Default)]
+pub struct Solution {
+    /// The processes, in the order of the model.
+    pub processes: Vec<ProcessSolution>,
+    pub materials: HashMap<MaterialPrototype, MaterialRates>,
+    /// How the objective would follow the limits, when asked for. Measuring it solves the
+    /// model again for each limit, see `Model::get_sensitivity`.
+    pub sensitivity: Option<Sensitivity>,
+}
+
+impl Solution {
+    /// Builds the solution of processes running the given crafts per second.
+    pub fn new(
+        crafts: impl IntoIterator<Item = (Process, f64)>,
+        surface: Option<&str>,
+        bonuses: &ProductivityBonuses,
+    ) -> Self {
+        let mut solution = Solution::default();
+        for (process, crafts) in crafts {
+            let amounts: HashMap<MaterialPrototype, f64> = process
+                .get_net_amounts(surface, bonuses)
+                .into_iter()
+                .map(|(material, amount)| (material, amount * crafts))
+                .collect();
+            for (material, &amount) in amounts.iter() {
+                let rates = solution.materials.entry(material.clone()).or_default();
+                if amount > 0.0 {
+                    rates.produced += amount;
+                } else {
+                    rates.consumed -= amount;
+                }
+            }
+            solution.processes.push(ProcessSolution {
+                crafts,
+                machines: process.get_machine_count(crafts, surface),
+                speed: process.get_speed(surface),
+                productivity: process.get_productivity(surface, bonuses),
+                consumption: process.get_effects(surface).consumption.unwrap_or(0.0) as f64,
+                power: process.get_electricity(surface) * crafts,
+                emissions: process
+                    .get_emissions(surface)
+                    .into_iter()
+                    .map(|(pollutant, amount)| (pollutant, amount * crafts * 60.0))
+                    .collect(),
+                amounts,
+                process,
+            });
+        }
+        solution
+    }
+
+    /// Returns the emissions of each pollutant per minute of the whole factory.
+    pub fn get_emissions(&self) -> HashMap<String, f64> {
+        let mut emissions: HashMap<String, f64> = HashMap::new();
+        for process in self.processes.iter() {
+            for (pollutant, amount) in process.emissions.iter() {
+                *emissions.entry(pollutant.clone()).or_default() += amount;
+            }
+        }
+        emissions
+    }
+
+    /// Returns what the solution makes of each material beyond the `outputs`: surpluses are
+    /// positive and deficits, taken in from outside, negative. Materials that balance are left
+    /// out, and the rest sorted by material.
+    pub fn get_imbalances(
+        &self,
+        outputs: &HashMap<MaterialPrototype, f64>,
+    ) -> Vec<(MaterialPrototype, f64)> {
+        let mut amounts: HashMap<MaterialPrototype, f64> = self
+            .materials
+            .iter()
+            .map(|(material, rates)| (material.clone(), rates.get_net()))
+            .collect();
+        for (material, &amount) in outputs.iter() {
+            *amounts.entry(material.clone()).or_default() -= amount;
+        }
+        let mut imbalances: Vec<(MaterialPrototype, f64)> = amounts
+            .into_iter()
+            .filter(|(_, amount)| amount.abs() > 1e-6)
+            .collect();
+        imbalances.sort_by_key(|(material, _)| material.to_id());
+        imbalances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn reports_solution() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                },
+                "assembling-machine": {
+                    "assembling-machine": {
+                        "name": "assembling-machine",
+                        "energy_usage": "100kW",
+                        "crafting_speed": 0.5,
+                        "crafting_categories": ["crafting"],
+                        "energy_source": {
+                            "type": "electric",
+                            "drain": "0W",
+                            "emissions_per_minute": {"pollution": 4}
+                        }
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let mut model = Model {
+            processes: vec![Process {
+                machine: Some("assembling-machine".into()),
+                ..Process::new(ProcessType::Recipe, "iron-gear-wheel".into())
+            }],
+            outputs: HashMap::from([(item("iron-gear-wheel"), 2.0)]),
+            ..Default::default()
+        };
+        let ModelResult::OneSolution(solution) = model.solve(true) else {
+            panic!("the model should have a single solution");
+        };
+        let [process] = solution.processes.as_slice() else {
+            panic!("{:?}", solution.processes);
+        };
+        assert!((process.crafts - 2.0).abs() < 1e-6);
+        assert!((process.machines - 2.0).abs() < 1e-6);
+        assert!((process.speed - 0.5).abs() < 1e-6);
+        assert!((process.power + 0.2).abs() < 1e-6);
+        assert!((solution.get_emissions()["pollution"] - 8.0).abs() < 1e-6);
+        let plates = solution.materials[&item("iron-plate")];
+        assert!((plates.consumed - 4.0).abs() < 1e-6);
+        assert!((plates.get_net() + 4.0).abs() < 1e-6);
+        let [(power, _), (material, amount)] =
+            solution.get_imbalances(&model.outputs).try_into().unwrap();
+        assert_eq!(power, MaterialPrototype::Electricity);
+        assert_eq!(material, item("iron-plate"));
+        assert!((amount + 4.0).abs() < 1e-6);
+    }
+}
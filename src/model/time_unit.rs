@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// The time unit rates are entered and shown in. The solver always works per second.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeUnit {
+    #[default]
+    Second,
+    Minute,
+    Hour,
+}
+
+pub const TIME_UNITS: [TimeUnit; 3] = [TimeUnit::Second, TimeUnit::Minute, TimeUnit::Hour];
+
+impl From<&TimeUnit> for String {
+    fn from(time_unit: &TimeUnit) -> Self {
+        match time_unit {
+            TimeUnit::Second => "per second",
+            TimeUnit::Minute => "per minute",
+            TimeUnit::Hour => "per hour",
+        }
+        .into()
+    }
+}
+
+impl TryFrom<&String> for TimeUnit {
+    type Error = &'static str;
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        TIME_UNITS
+            .into_iter()
+            .find(|time_unit| String::from(time_unit) == *value)
+            .ok_or("Unknown time unit")
+    }
+}
+
+impl TimeUnit {
+    pub fn get_seconds(self) -> f64 {
+        match self {
+            TimeUnit::Second => 1.0,
+            TimeUnit::Minute => 60.0,
+            TimeUnit::Hour => 3600.0,
+        }
+    }
+
+    /// Converts a rate per second into this unit.
+    pub fn convert(self, rate: f64) -> f64 {
+        rate * self.get_seconds()
+    }
+
+    /// Converts a rate in this unit into a rate per second.
+    pub fn to_per_second(self, rate: f64) -> f64 {
+        rate / self.get_seconds()
+    }
+
+    /// Formats a rate per second in this unit, e.g. `1.5 per minute`.
+    pub fn format(self, rate: f64) -> String {
+        format!("{} {}", self.convert(rate), String::from(&self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::model::time_unit::TimeUnit;
+    use crate::model::Model;
+    use std::collections::HashMap;
+
+    #[test]
+    fn converts_rates() {
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let model = Model {
+            time_unit: TimeUnit::Minute,
+            time_units: HashMap::from([(item("iron-plate"), TimeUnit::Hour)]),
+            ..Default::default()
+        };
+        assert_eq!(model.get_time_unit(&item("iron-ore")), TimeUnit::Minute);
+        assert_eq!(model.get_time_unit(&item("iron-plate")), TimeUnit::Hour);
+        assert_eq!(TimeUnit::Minute.format(0.5), "30 per minute");
+        assert!((TimeUnit::Hour.to_per_second(7200.0) - 2.0).abs() < 1e-9);
+        assert!((TimeUnit::Hour.convert(TimeUnit::Hour.to_per_second(90.0)) - 90.0).abs() < 1e-9);
+        assert_eq!(
+            TimeUnit::try_from(&"per hour".to_string()),
+            Ok(TimeUnit::Hour)
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use crate::data::materials::MaterialPrototype;
+use crate::data::ProcessType;
+use crate::model::solution::Solution;
+use crate::model::{buy_least, copy_variables, Model, ModelResult, Problem};
+use good_lp::{microlp, variable, Expression, IntoAffineExpression, Solution as _, SolverModel};
+
+/// How `Model::solve_whole_machines` finds whole machine counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// The fewest machines in all, found by the solver as integers. Optimal, but slow on
+    /// large models.
+    Exact,
+    /// The machine counts of the solution, each rounded up.
+    RoundUp,
+}
+
+impl Model {
+    /// Solves the model with whole machines. Once the counts are found, the machines run at
+    /// full speed as far as their ingredients allow, and the rest of the model is solved again
+    /// around them. Void processes keep any rate and any machine count.
+    pub fn solve_whole_machines(&self, generate_inputs: bool, rounding: Rounding) -> ModelResult {
+        let surface = self.surface.as_deref();
+        // The machines one craft per second takes, for processes running in whole machines.
+        let machines_per_craft: Vec<Option<f64>> = self
+            .processes
+            .iter()
+            .map(|process| {
+                (process.process_type != ProcessType::Void)
+                    .then(|| process.get_machine_count(1.0, surface))
+                    .filter(|&machines| machines > 0.0)
+            })
+            .collect();
+        let crafts = match rounding {
+            Rounding::Exact => self.solve_integer(generate_inputs, &machines_per_craft),
+            Rounding::RoundUp => self.solve_rounded_up(generate_inputs),
+        };
+        let Some(crafts) = crafts else {
+            return ModelResult::NoSolution(self.diagnose(generate_inputs, false));
+        };
+        let machines: Vec<Option<f64>> = crafts
+            .iter()
+            .zip(machines_per_craft.iter())
+            .map(|(&crafts, machines_per_craft)| {
+                machines_per_craft
+                    .map(|machines_per_craft| (crafts * machines_per_craft - 1e-6).ceil().max(0.0))
+            })
+            .collect();
+        let Some(crafts) =
+            self.solve_at_full_speed(generate_inputs, &machines_per_craft, &machines)
+        else {
+            return ModelResult::NoSolution(self.diagnose(generate_inputs, false));
+        };
+        let mut solution = Solution::new(
+            self.processes.iter().cloned().zip(crafts),
+            surface,
+            &self.get_productivity_bonuses(),
+        );
+        for (process, machines) in solution.processes.iter_mut().zip(machines) {
+            if let Some(machines) = machines {
+                process.machines = machines;
+            }
+        }
+        let overproduction: Vec<(MaterialPrototype, f64)> = solution
+            .get_imbalances(&self.outputs)
+            .into_iter()
+            .filter(|&(_, amount)| amount > 0.0)
+            .collect();
+        ModelResult::WholeMachines {
+            solution,
+            overproduction,
+        }
+    }
+
+    /// Returns the crafts per second of each process, with the processes in whole `machines`
+    /// running as fast as their ingredients allow. The objective then picks among the
+    /// solutions keeping the machines that busy.
+    fn solve_at_full_speed(
+        &self,
+        generate_inputs: bool,
+        machines_per_craft: &[Option<f64>],
+        machines: &[Option<f64>],
+    ) -> Option<Vec<f64>> {
+        let Problem {
+            variables,
+            processes,
+            mut constraints,
+            bought_variables,
+            objective,
+            ..
+        } = self.build_problem(generate_inputs, false, false);
+        let mut busy_machines = Expression::from(0);
+        for ((&process, &machines_per_craft), &machines) in
+            processes.iter().zip(machines_per_craft).zip(machines)
+        {
+            let (Some(machines_per_craft), Some(machines)) = (machines_per_craft, machines) else {
+                continue;
+            };
+            busy_machines += process * machines_per_craft;
+            constraints.push(Box::new(move || {
+                (process * machines_per_craft).leq(machines)
+            }));
+        }
+        let most = copy_variables(&variables)
+            .maximise(busy_machines.clone())
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+            .ok()?
+            .eval(busy_machines.clone());
+        let least = most - 1e-9 * most.abs().max(1.0);
+        constraints.push(Box::new(move || busy_machines.clone().geq(least)));
+        buy_least(&variables, &mut constraints, &bought_variables).ok()?;
+        let results = copy_variables(&variables)
+            .minimise(objective)
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+            .ok()?;
+        Some(
+            processes
+                .iter()
+                .map(|&process| results.value(process))
+                .collect(),
+        )
+    }
+
+    /// Returns the crafts per second of each process, with the fewest machines in all once
+    /// each count is rounded up to a whole machine. The objective then picks among the
+    /// solutions with that many machines.
+    fn solve_integer(
+        &self,
+        generate_inputs: bool,
+        machines_per_craft: &[Option<f64>],
+    ) -> Option<Vec<f64>> {
+        let Problem {
+            mut variables,
+            processes,
+            mut constraints,
+            bought_variables,
+            objective,
+            ..
+        } = self.build_problem(generate_inputs, false, false);
+        let mut machine_count = Expression::from(0);
+        for (&process, &machines_per_craft) in processes.iter().zip(machines_per_craft) {
+            let Some(machines_per_craft) = machines_per_craft else {
+                continue;
+            };
+            let machines = variables.add(variable().integer().min(0));
+            machine_count += machines;
+            constraints.push(Box::new(move || {
+                machines.into_expression().geq(process * machines_per_craft)
+            }));
+        }
+        let fewest = copy_variables(&variables)
+            .minimise(machine_count.clone())
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+            .ok()?
+            .eval(machine_count.clone());
+        constraints.push(Box::new(move || machine_count.clone().leq(fewest + 0.5)));
+        buy_least(&variables, &mut constraints, &bought_variables).ok()?;
+        let results = copy_variables(&variables)
+            .minimise(objective)
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+            .ok()?;
+        Some(
+            processes
+                .iter()
+                .map(|&process| results.value(process))
+                .collect(),
+        )
+    }
+
+    /// Returns the crafts per second of each process in the solution of the model, whose
+    /// machine counts are then rounded up.
+    fn solve_rounded_up(&self, generate_inputs: bool) -> Option<Vec<f64>> {
+        let Problem {
+            variables,
+            processes,
+            mut constraints,
+            bought_variables,
+            objective,
+            ..
+        } = self.build_problem(generate_inputs, false, false);
+        buy_least(&variables, &mut constraints, &bought_variables).ok()?;
+        let results = copy_variables(&variables)
+            .minimise(objective)
+            .using(microlp)
+            .with_all(constraints.iter().map(|function| function()))
+            .solve()
+            .ok()?;
+        Some(
+            processes
+                .iter()
+                .map(|&process| results.value(process))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{set_test_registry, Process, ProcessType};
+    use crate::model::balance::MaterialBalance;
+    use crate::model::whole_machines::Rounding;
+    use crate::model::{Model, ModelResult};
+    use std::collections::HashMap;
+
+    #[test]
+    fn solves_whole_machines() {
+        let _registry = set_test_registry(
+            r#"{
+                "recipe": {
+                    "iron-plate": {
+                        "name": "iron-plate",
+                        "category": "smelting",
+                        "energy_required": 3.2,
+                        "ingredients": [{"type": "item", "name": "iron-ore", "amount": 1}],
+                        "results": [{"type": "item", "name": "iron-plate", "amount": 1}]
+                    },
+                    "iron-gear-wheel": {
+                        "name": "iron-gear-wheel",
+                        "energy_required": 0.5,
+                        "ingredients": [{"type": "item", "name": "iron-plate", "amount": 2}],
+                        "results": [{"type": "item", "name": "iron-gear-wheel", "amount": 1}]
+                    }
+                },
+                "furnace": {
+                    "furnace": {
+                        "name": "furnace",
+                        "energy_usage": "90kW",
+                        "crafting_speed": 1,
+                        "crafting_categories": ["smelting"],
+                        "energy_source": {"type": "void"}
+                    }
+                },
+                "assembling-machine": {
+                    "assembling-machine": {
+                        "name": "assembling-machine",
+                        "energy_usage": "150kW",
+                        "crafting_speed": 0.75,
+                        "crafting_categories": ["crafting"],
+                        "energy_source": {"type": "void"}
+                    }
+                }
+            }"#,
+        );
+        let item = |name: &str| MaterialPrototype::Item(name.into(), "normal".into());
+        let model = Model {
+            processes: vec![
+                Process {
+                    machine: Some("furnace".into()),
+                    ..Process::new(ProcessType::Recipe, "iron-plate".into())
+                },
+                Process {
+                    machine: Some("assembling-machine".into()),
+                    ..Process::new(ProcessType::Recipe, "iron-gear-wheel".into())
+                },
+            ],
+            outputs: HashMap::from([(item("iron-gear-wheel"), 2.0)]),
+            ..Default::default()
+        };
+        for balance in [MaterialBalance::Overflow, MaterialBalance::Balanced] {
+            let model = Model {
+                balances: HashMap::from([(item("iron-plate"), balance)]),
+                ..model.clone()
+            };
+            for rounding in [Rounding::Exact, Rounding::RoundUp] {
+                let ModelResult::WholeMachines {
+                    solution,
+                    overproduction,
+                } = model.solve_whole_machines(true, rounding)
+                else {
+                    panic!("{:?} should find whole machines", rounding);
+                };
+                let machines: Vec<f64> = solution
+                    .processes
+                    .iter()
+                    .map(|process| process.machines)
+                    .collect();
+                // 4 plates per second take 12.8 furnaces, 2 gears 1.33 assembling machines
+                assert!((machines[0] - 13.0).abs() < 1e-6, "{:?}", machines);
+                assert!((machines[1] - 2.0).abs() < 1e-6, "{:?}", machines);
+                // 13 furnaces at full speed make 4.0625 plates, turned into 2.03125 gears
+                let crafts: Vec<f64> = solution
+                    .processes
+                    .iter()
+                    .map(|process| process.crafts)
+                    .collect();
+                assert!((crafts[0] - 4.0625).abs() < 1e-6, "{:?}", crafts);
+                assert!((crafts[1] - 2.03125).abs() < 1e-6, "{:?}", crafts);
+                let [(gears, amount)] = overproduction.as_slice() else {
+                    panic!("{:?}", overproduction);
+                };
+                assert_eq!(*gears, item("iron-gear-wheel"));
+                assert!((amount - 0.03125).abs() < 1e-6, "{}", amount);
+            }
+        }
+    }
+}
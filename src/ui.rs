@@ -1,44 +1,58 @@
 use crate::data::data_loader::load_data;
-use crate::data::effects::EffectReceiver;
 use crate::data::materials::MaterialPrototype;
-use crate::data::{get_registry, set_registry, Process, ProcessType, Registry};
+use crate::data::quality::{get_quality_names, NORMAL_QUALITY};
+use crate::data::surfaces::check_surface_conditions;
+use crate::data::technologies::{
+    get_unlocking_technologies, is_recipe_unlocked, research, unresearch,
+};
+use crate::data::{get_registry, set_registry, Beacon, Process, ProcessType};
+use crate::model::balance::{MaterialBalance, MATERIAL_BALANCES};
+use crate::model::limit::{Limit, LIMIT_KINDS};
+use crate::model::objective::{Objective, OBJECTIVES};
+use crate::model::quality_loop::QualityLoop;
+use crate::model::ratio::Ratio;
+use crate::model::recipe_rules::{RecipeRule, RECIPE_RULES};
+use crate::model::research::get_research_reports;
+use crate::model::solution::Solution;
+use crate::model::time_unit::{TimeUnit, TIME_UNITS};
+use crate::model::whole_machines::Rounding;
 use crate::model::{Model, ModelResult};
+use crate::ui::save_data::{read_project, SaveData};
 use native_dialog::DialogBuilder;
 use ratatui::crossterm::event::KeyModifiers;
 use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use ratatui::{prelude::*, widgets::*};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::path::PathBuf;
 use std::{fs, iter, panic};
 use tap::Tap;
 
-#[derive(Serialize, Deserialize)]
-struct SaveData {
-    registry: Registry,
-    model: Model,
-    process_data: HashMap<(ProcessType, String), ProcessData>,
-}
+mod save_data;
 
-#[derive(Serialize, Deserialize, Clone)]
-struct Beacon {
-    prototype: String,
-    count: u16,
-    modules: Vec<String>,
-}
-#[derive(Clone, Serialize, Deserialize)]
-enum ProcessData {
-    Resource {
-        mining_drill: Option<String>,
-        modules: Vec<String>,
-        beacons: HashMap<String, Beacon>,
-    },
-    Recipe {
-        crafting_machine: Option<String>,
-        modules: Vec<String>,
-        beacons: HashMap<String, Beacon>,
-    },
+const ANY_SURFACE: &str = "Any surface";
+const TRACK_RESEARCH: &str = "Track research";
+const STOP_TRACKING_RESEARCH: &str = "Stop tracking research";
+const RESEARCH_LEVELS: &str = "Research Levels";
+const MATERIAL_COSTS: &str = "Material Costs";
+const PROJECT_TIME_UNIT: &str = "Project time unit";
+const AUTOMATIC_RECIPE: &str = "Automatic";
+const CATEGORY_RULE: &str = "Category rule";
+const NO_LIMIT: &str = "No limit";
+const NEW_RATIO: &str = "New Ratio";
+
+#[derive(Clone, Copy)]
+enum SolveMode {
+    Minimise,
+    GenerateInputs,
+    /// Maximise the weighted outputs within the inputs.
+    MaximiseOutputs,
+    /// Maximise the outputs within the inputs, keeping the ratios of their amounts.
+    MaximiseRatios,
+    /// Generate the inputs of a factory of whole machines.
+    WholeMachines(Rounding),
+    /// Minimise, then measure the shadow prices and the reduced costs of the solution.
+    Sensitivity,
 }
 
 struct Menu {
@@ -57,12 +71,18 @@ struct App {
     scroll_start_time: Option<std::time::Instant>,
     last_scroll_time: Option<std::time::Instant>,
     model: Model,
-    process_data: HashMap<(ProcessType, String), ProcessData>,
     selected_process: Option<String>,
     fill_modules: bool,
     selected_beacon: Option<String>,
     selected_material: Option<(MaterialPrototype, f64)>,
+    selected_technology: Option<String>,
+    /// The recipe, or recipe category, whose rule is being set.
+    selected_recipe: Option<String>,
+    /// The index of the ratio being edited.
+    selected_ratio: Option<usize>,
     number_input: Option<(String, String)>,
+    /// The text being typed and what it is for, like `number_input`.
+    text_input: Option<(String, String)>,
     search_query: Option<String>,
     message_scroll: usize,
 }
@@ -79,20 +99,35 @@ impl App {
             handle_click: |app: &mut App, name: String| match name.as_str() {
                 "Select data file" => {
                     match DialogBuilder::file()
-                        .add_filter("Json file", &["json"])
+                        .add_filter("Json file", ["json"])
                         .open_single_file()
                         .show()
                         .unwrap()
                     {
                         Some(file) => match load_data(file.to_str().unwrap().to_string()) {
-                            Ok(registry) => {
+                            Ok((registry, warnings)) => {
+                                let mut message = format!(
+                                    "Loaded data file: {} recipes, {} items, {} fluids, {} machines.",
+                                    registry.recipes.len(),
+                                    registry.items.len(),
+                                    registry.fluids.len(),
+                                    registry.crafting_machines.len()
+                                        + registry.mining_drills.len()
+                                        + registry.labs.len(),
+                                );
+                                if !warnings.is_empty() {
+                                    message += &format!("\nWarnings ({}):", warnings.len());
+                                    for warning in warnings {
+                                        message += &format!("\n{}", warning);
+                                    }
+                                }
                                 set_registry(registry);
-                                app.set_message("Loaded data file.");
+                                app.set_message(message);
                                 let menu = app.get_project_menu();
                                 app.menu_stack.push((menu, 0));
                             }
-                            Err(_) => {
-                                app.set_message("Failed to load file!");
+                            Err(error) => {
+                                app.set_message(format!("Failed to load file! {}", error));
                             }
                         },
                         None => {
@@ -120,11 +155,14 @@ impl App {
             last_scroll_time: None,
             model: Model::default(),
             selected_process: None,
-            process_data: Default::default(),
             fill_modules: false,
             selected_beacon: None,
             selected_material: None,
+            selected_technology: None,
+            selected_recipe: None,
+            selected_ratio: None,
             number_input: None,
+            text_input: None,
             search_query: None,
             message_scroll: 0,
         }
@@ -137,9 +175,23 @@ impl App {
                 "Add Process".into(),
                 "Outputs".into(),
                 "Inputs".into(),
+                "Output Ratios".into(),
+                "Quality Loops".into(),
+                "Surface".into(),
+                "Research".into(),
+                "Objective".into(),
+                "Material Balance".into(),
+                "Time Unit".into(),
+                "Recipe Rules".into(),
+                "Auto Build".into(),
                 // "Auto Inputs".into(),
                 "Solve Model".into(),
                 "Solve Model - Generate inputs".into(),
+                "Solve Model - Maximise outputs".into(),
+                "Solve Model - Maximise outputs in ratio".into(),
+                "Solve Model - Whole machines".into(),
+                "Solve Model - Whole machines (round up)".into(),
+                "Solve Model - Shadow prices".into(),
                 "Save Project".into(),
             ],
             handle_click: |app: &mut App, name: String| match name.as_str() {
@@ -159,11 +211,62 @@ impl App {
                     let menu = app.get_inputs_menu();
                     app.menu_stack.push((menu, 0));
                 }
+                "Output Ratios" => {
+                    let menu = app.get_ratios_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Quality Loops" => {
+                    let menu = app.get_quality_loops_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Surface" => {
+                    let menu = app.get_surface_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Research" => {
+                    let menu = app.get_research_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Objective" => {
+                    let menu = app.get_objective_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Material Balance" => {
+                    let menu = app.get_balances_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Time Unit" => {
+                    let menu = app.get_time_unit_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Recipe Rules" => {
+                    let menu = app.get_recipe_rules_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Auto Build" => {
+                    let menu = app.get_auto_build_menu();
+                    app.menu_stack.push((menu, 0));
+                }
                 "Solve Model" => {
-                    app.solve_model(false);
+                    app.solve_model(SolveMode::Minimise);
                 }
                 "Solve Model - Generate inputs" => {
-                    app.solve_model(true);
+                    app.solve_model(SolveMode::GenerateInputs);
+                }
+                "Solve Model - Maximise outputs" => {
+                    app.solve_model(SolveMode::MaximiseOutputs);
+                }
+                "Solve Model - Maximise outputs in ratio" => {
+                    app.solve_model(SolveMode::MaximiseRatios);
+                }
+                "Solve Model - Whole machines" => {
+                    app.solve_model(SolveMode::WholeMachines(Rounding::Exact));
+                }
+                "Solve Model - Whole machines (round up)" => {
+                    app.solve_model(SolveMode::WholeMachines(Rounding::RoundUp));
+                }
+                "Solve Model - Shadow prices" => {
+                    app.solve_model(SolveMode::Sensitivity);
                 }
                 "Save Project" => {
                     app.save_project(true);
@@ -173,40 +276,462 @@ impl App {
             update_menu: Some(|app: &mut App| app.get_project_menu()),
         }
     }
+    fn get_surface_menu(&mut self) -> Menu {
+        Menu {
+            title: format!(
+                "Surface ({})",
+                self.model.surface.as_deref().unwrap_or(ANY_SURFACE)
+            ),
+            items: iter::once(ANY_SURFACE.to_string())
+                .chain(
+                    get_registry()
+                        .surfaces
+                        .keys()
+                        .cloned()
+                        .collect::<Vec<String>>()
+                        .tap_mut(|vec| vec.sort()),
+                )
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                app.model.surface = (name != ANY_SURFACE).then_some(name);
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_surface_menu()),
+        }
+    }
+    fn get_objective_menu(&mut self) -> Menu {
+        Menu {
+            title: format!("Minimise ({})", String::from(&self.model.objective)),
+            items: OBJECTIVES
+                .iter()
+                .map(String::from)
+                .chain(iter::once(MATERIAL_COSTS.into()))
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                if name == MATERIAL_COSTS {
+                    let menu = app.get_costs_menu();
+                    app.menu_stack.push((menu, 0));
+                } else {
+                    app.model.objective = Objective::try_from(&name).unwrap();
+                    app.menu_stack.pop();
+                }
+            },
+            update_menu: Some(|app: &mut App| app.get_objective_menu()),
+        }
+    }
+    fn get_costs_menu(&mut self) -> Menu {
+        let surface = self.model.surface.clone();
+        let bonuses = self.model.get_productivity_bonuses();
+        Menu {
+            title: "Material Costs (per unit consumed)".into(),
+            items: self
+                .model
+                .processes
+                .iter()
+                .flat_map(|process| process.get_net_amounts(surface.as_deref(), &bonuses))
+                .map(|(material, _)| material)
+                .collect::<HashSet<MaterialPrototype>>()
+                .into_iter()
+                .map(|material| {
+                    let cost = self.model.costs.get(&material).copied().unwrap_or(0.0);
+                    material.to_id() + ": " + &*cost.to_string()
+                })
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let (material, cost) = name.split_once(": ").unwrap();
+                app.selected_material = Some((
+                    MaterialPrototype::from_id(material).unwrap(),
+                    cost.parse().unwrap(),
+                ));
+                app.number_input = Some(("material_cost".into(), cost.into()));
+            },
+            update_menu: Some(|app: &mut App| app.get_costs_menu()),
+        }
+    }
+    fn get_balances_menu(&mut self) -> Menu {
+        let surface = self.model.surface.clone();
+        let bonuses = self.model.get_productivity_bonuses();
+        Menu {
+            title: "Material Balance".into(),
+            items: self
+                .model
+                .processes
+                .iter()
+                .flat_map(|process| process.get_net_amounts(surface.as_deref(), &bonuses))
+                .map(|(material, _)| material)
+                .collect::<HashSet<MaterialPrototype>>()
+                .into_iter()
+                .map(|material| {
+                    material.to_id() + ": " + &String::from(&self.model.get_balance(&material))
+                })
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let (material, _) = name.split_once(": ").unwrap();
+                app.selected_material = Some((MaterialPrototype::from_id(material).unwrap(), 0.0));
+                let menu = app.get_balance_menu();
+                app.menu_stack.push((menu, 0));
+            },
+            update_menu: Some(|app: &mut App| app.get_balances_menu()),
+        }
+    }
+    fn get_balance_menu(&mut self) -> Menu {
+        let (material, _) = self.selected_material.as_ref().unwrap();
+        Menu {
+            title: format!(
+                "{} ({})",
+                material.to_id(),
+                String::from(&self.model.get_balance(material))
+            ),
+            items: MATERIAL_BALANCES.iter().map(String::from).collect(),
+            handle_click: |app: &mut App, name: String| {
+                let (material, _) = app.selected_material.clone().unwrap();
+                match MaterialBalance::try_from(&name).unwrap() {
+                    MaterialBalance::Overflow => app.model.balances.remove(&material),
+                    balance => app.model.balances.insert(material, balance),
+                };
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_balance_menu()),
+        }
+    }
+    fn get_time_unit_menu(&mut self) -> Menu {
+        Menu {
+            title: format!("Time Unit ({})", String::from(&self.model.time_unit)),
+            items: TIME_UNITS.iter().map(String::from).collect(),
+            handle_click: |app: &mut App, name: String| {
+                app.model.time_unit = TimeUnit::try_from(&name).unwrap();
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_time_unit_menu()),
+        }
+    }
+    fn get_material_time_unit_menu(&mut self) -> Menu {
+        let (material, _) = self.selected_material.as_ref().unwrap();
+        Menu {
+            title: format!(
+                "{} ({})",
+                material.to_id(),
+                String::from(&self.model.get_time_unit(material))
+            ),
+            items: iter::once(PROJECT_TIME_UNIT.into())
+                .chain(TIME_UNITS.iter().map(String::from))
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                let (material, _) = app.selected_material.clone().unwrap();
+                if name == PROJECT_TIME_UNIT {
+                    app.model.time_units.remove(&material);
+                } else {
+                    let time_unit = TimeUnit::try_from(&name).unwrap();
+                    app.model.time_units.insert(material, time_unit);
+                }
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_material_time_unit_menu()),
+        }
+    }
+    fn get_auto_build_menu(&mut self) -> Menu {
+        Menu {
+            title: "Auto Build".into(),
+            items: vec![
+                "Build From Outputs".into(),
+                "Build With All Alternatives".into(),
+                "Preferred Recipes".into(),
+            ],
+            handle_click: |app: &mut App, name: String| match name.as_str() {
+                "Build From Outputs" | "Build With All Alternatives" => {
+                    let process_count = app.model.processes.len();
+                    let unmade = app.model.auto_build(name == "Build With All Alternatives");
+                    let mut message = format!(
+                        "Added {} processes",
+                        app.model.processes.len() - process_count
+                    );
+                    if !unmade.is_empty() {
+                        message += "\nNothing makes, add them as inputs:\n";
+                        message += &unmade
+                            .iter()
+                            .map(MaterialPrototype::to_id)
+                            .collect::<Vec<String>>()
+                            .join(", ");
+                    }
+                    app.set_message(message);
+                }
+                "Preferred Recipes" => {
+                    let menu = app.get_preferred_recipes_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                _ => {}
+            },
+            update_menu: None,
+        }
+    }
+    fn get_preferred_recipes_menu(&mut self) -> Menu {
+        Menu {
+            title: "Preferred Recipes".into(),
+            items: self
+                .model
+                .processes
+                .iter()
+                .flat_map(|process| process.get_ingredients())
+                .map(|material| material.get_prototype())
+                .chain(self.model.outputs.keys().cloned())
+                .chain(self.model.preferred_recipes.keys().cloned())
+                .collect::<HashSet<MaterialPrototype>>()
+                .into_iter()
+                .filter(|material| self.model.get_recipes_making(material).len() > 1)
+                .map(|material| {
+                    material.to_id()
+                        + ": "
+                        + self
+                            .model
+                            .preferred_recipes
+                            .get(&material)
+                            .map_or(AUTOMATIC_RECIPE, String::as_str)
+                })
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let (material, _) = name.split_once(": ").unwrap();
+                app.selected_material = Some((MaterialPrototype::from_id(material).unwrap(), 0.0));
+                let menu = app.get_preferred_recipe_menu();
+                app.menu_stack.push((menu, 0));
+            },
+            update_menu: Some(|app: &mut App| app.get_preferred_recipes_menu()),
+        }
+    }
+    fn get_preferred_recipe_menu(&mut self) -> Menu {
+        let (material, _) = self.selected_material.as_ref().unwrap();
+        Menu {
+            title: format!("Recipe for {}", material.to_id()),
+            items: iter::once(AUTOMATIC_RECIPE.into())
+                .chain(self.model.get_recipes_making(material))
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                let (material, _) = app.selected_material.clone().unwrap();
+                if name == AUTOMATIC_RECIPE {
+                    app.model.preferred_recipes.remove(&material);
+                } else {
+                    app.model.preferred_recipes.insert(material, name);
+                }
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_preferred_recipe_menu()),
+        }
+    }
+    fn get_recipe_rules_menu(&mut self) -> Menu {
+        Menu {
+            title: "Recipe Rules".into(),
+            items: vec!["Recipes".into(), "Recipe Categories".into()],
+            handle_click: |app: &mut App, name: String| {
+                let menu = match name.as_str() {
+                    "Recipes" => app.get_recipes_rules_menu(),
+                    _ => app.get_categories_rules_menu(),
+                };
+                app.menu_stack.push((menu, 0));
+            },
+            update_menu: None,
+        }
+    }
+    fn get_recipes_rules_menu(&mut self) -> Menu {
+        Menu {
+            title: "Recipes".into(),
+            items: get_registry()
+                .recipes
+                .keys()
+                .map(|name| name.clone() + ": " + &String::from(&self.model.get_recipe_rule(name)))
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let (recipe, _) = name.split_once(": ").unwrap();
+                app.selected_recipe = Some(recipe.into());
+                let menu = app.get_recipe_rule_menu();
+                app.menu_stack.push((menu, 0));
+            },
+            update_menu: Some(|app: &mut App| app.get_recipes_rules_menu()),
+        }
+    }
+    fn get_recipe_rule_menu(&mut self) -> Menu {
+        let recipe = self.selected_recipe.as_ref().unwrap();
+        Menu {
+            title: format!(
+                "{} ({})",
+                recipe,
+                String::from(&self.model.get_recipe_rule(recipe))
+            ),
+            items: iter::once(CATEGORY_RULE.into())
+                .chain(RECIPE_RULES.iter().map(String::from))
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                let recipe = app.selected_recipe.clone().unwrap();
+                if name == CATEGORY_RULE {
+                    app.model.recipe_rules.remove(&recipe);
+                } else {
+                    let rule = RecipeRule::try_from(&name).unwrap();
+                    app.model.recipe_rules.insert(recipe, rule);
+                }
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_recipe_rule_menu()),
+        }
+    }
+    fn get_categories_rules_menu(&mut self) -> Menu {
+        Menu {
+            title: "Recipe Categories".into(),
+            items: get_registry()
+                .recipes
+                .values()
+                .map(|recipe| &recipe.category)
+                .collect::<HashSet<&String>>()
+                .into_iter()
+                .map(|category| {
+                    category.clone()
+                        + ": "
+                        + &String::from(
+                            &self
+                                .model
+                                .category_rules
+                                .get(category)
+                                .copied()
+                                .unwrap_or_default(),
+                        )
+                })
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let (category, _) = name.split_once(": ").unwrap();
+                app.selected_recipe = Some(category.into());
+                let menu = app.get_category_rule_menu();
+                app.menu_stack.push((menu, 0));
+            },
+            update_menu: Some(|app: &mut App| app.get_categories_rules_menu()),
+        }
+    }
+    fn get_category_rule_menu(&mut self) -> Menu {
+        let category = self.selected_recipe.as_ref().unwrap();
+        Menu {
+            title: format!(
+                "{} ({})",
+                category,
+                String::from(
+                    &self
+                        .model
+                        .category_rules
+                        .get(category)
+                        .copied()
+                        .unwrap_or_default()
+                )
+            ),
+            items: RECIPE_RULES.iter().map(String::from).collect(),
+            handle_click: |app: &mut App, name: String| {
+                let category = app.selected_recipe.clone().unwrap();
+                match RecipeRule::try_from(&name).unwrap() {
+                    RecipeRule::Allow => app.model.category_rules.remove(&category),
+                    rule => app.model.category_rules.insert(category, rule),
+                };
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_category_rule_menu()),
+        }
+    }
+    fn get_research_menu(&mut self) -> Menu {
+        Menu {
+            title: "Research (select to toggle)".into(),
+            items: match &self.model.researched {
+                None => vec![TRACK_RESEARCH.into(), RESEARCH_LEVELS.into()],
+                Some(researched) => [STOP_TRACKING_RESEARCH.into(), RESEARCH_LEVELS.into()]
+                    .into_iter()
+                    .chain(
+                        get_registry()
+                            .technologies
+                            .keys()
+                            .collect::<Vec<&String>>()
+                            .tap_mut(|vec| vec.sort())
+                            .into_iter()
+                            .map(|name| get_checkbox_label(name, researched.contains(name))),
+                    )
+                    .collect(),
+            },
+            handle_click: |app: &mut App, name: String| match name.as_str() {
+                TRACK_RESEARCH => app.model.researched = Some(HashSet::new()),
+                STOP_TRACKING_RESEARCH => app.model.researched = None,
+                RESEARCH_LEVELS => {
+                    let menu = app.get_research_levels_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                _ => {
+                    let registry = get_registry();
+                    let researched = app.model.researched.as_mut().unwrap();
+                    let (checkbox, technology) = name.split_at(4);
+                    if checkbox == "[x] " {
+                        unresearch(&registry, researched, technology);
+                    } else {
+                        research(&registry, researched, technology);
+                    }
+                }
+            },
+            update_menu: Some(|app: &mut App| app.get_research_menu()),
+        }
+    }
+    fn get_research_levels_menu(&mut self) -> Menu {
+        Menu {
+            title: "Research Levels".into(),
+            items: get_registry()
+                .technologies
+                .values()
+                .filter(|technology| technology.max_level != Some(technology.level))
+                .map(|technology| {
+                    format!(
+                        "{}: {}",
+                        technology.name,
+                        self.model
+                            .research_levels
+                            .get(&technology.name)
+                            .copied()
+                            .unwrap_or(0)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let (technology, level) = name.split_once(": ").unwrap();
+                app.selected_technology = Some(technology.into());
+                app.number_input = Some(("research_level".into(), level.into()));
+            },
+            update_menu: Some(|app: &mut App| app.get_research_levels_menu()),
+        }
+    }
     //noinspection DuplicatedCode
     fn get_new_process_menu(&mut self, process_type: Option<ProcessType>) -> Menu {
         match process_type {
             Some(process_type) => {
                 let registry = get_registry();
+                let surface = self.model.surface.clone();
+                let researched = self.model.researched.clone();
+                let forbidden: HashSet<&String> = registry
+                    .recipes
+                    .keys()
+                    .filter(|name| self.model.get_recipe_rule(name) == RecipeRule::Forbid)
+                    .collect();
                 match process_type {
                     ProcessType::Resource => Menu {
                         title: "Add Resource".into(),
                         items: registry
                             .resources
                             .keys()
-                            .cloned()
-                            .filter(|name| {
+                            .filter(|&name| {
                                 self.get_process_from_name(
                                     &(Into::<String>::into(&ProcessType::Resource) + " :" + name),
                                 )
                                 .is_none()
                             })
+                            .cloned()
                             .collect::<Vec<String>>()
                             .tap_mut(|vec| vec.sort()),
                         handle_click: |app: &mut App, name: String| {
-                            app.model.processes.push(Process {
-                                process_type: ProcessType::Resource,
-                                name: name.clone(),
-                                productivity: 0.0,
-                            });
-                            app.process_data.insert(
-                                (ProcessType::Resource, name),
-                                ProcessData::Resource {
-                                    mining_drill: None,
-                                    modules: vec![],
-                                    beacons: Default::default(),
-                                },
-                            );
+                            app.model
+                                .processes
+                                .push(Process::new(ProcessType::Resource, name));
                             app.menu_stack.pop();
                             app.menu_stack.pop();
                         },
@@ -218,22 +743,28 @@ impl App {
                         title: "Add Plant".into(),
                         items: registry
                             .plants
-                            .keys()
-                            .cloned()
-                            .filter(|name| {
+                            .iter()
+                            .filter(|(_, plant)| {
+                                check_surface_conditions(
+                                    &registry,
+                                    &plant.surface_conditions,
+                                    surface.as_deref(),
+                                )
+                            })
+                            .map(|(name, _)| name)
+                            .filter(|&name| {
                                 self.get_process_from_name(
                                     &(Into::<String>::into(&ProcessType::Plant) + " :" + name),
                                 )
                                 .is_none()
                             })
+                            .cloned()
                             .collect::<Vec<String>>()
                             .tap_mut(|vec| vec.sort()),
                         handle_click: |app: &mut App, name: String| {
-                            app.model.processes.push(Process {
-                                process_type: ProcessType::Plant,
-                                name,
-                                productivity: 0.0,
-                            });
+                            app.model
+                                .processes
+                                .push(Process::new(ProcessType::Plant, name));
                             app.menu_stack.pop();
                             app.menu_stack.pop();
                         },
@@ -245,30 +776,29 @@ impl App {
                         title: "Add Recipe".into(),
                         items: registry
                             .recipes
-                            .keys()
-                            .cloned()
-                            .filter(|name| {
+                            .iter()
+                            .filter(|(name, recipe)| {
+                                check_surface_conditions(
+                                    &registry,
+                                    &recipe.surface_conditions,
+                                    surface.as_deref(),
+                                ) && is_recipe_unlocked(&registry, researched.as_ref(), name)
+                                    && !forbidden.contains(name)
+                            })
+                            .map(|(name, _)| name)
+                            .filter(|&name| {
                                 self.get_process_from_name(
                                     &(Into::<String>::into(&ProcessType::Recipe) + " :" + name),
                                 )
                                 .is_none()
                             })
+                            .cloned()
                             .collect::<Vec<String>>()
                             .tap_mut(|vec| vec.sort()),
                         handle_click: |app: &mut App, name: String| {
-                            app.model.processes.push(Process {
-                                process_type: ProcessType::Recipe,
-                                name: name.clone(),
-                                productivity: 0.0,
-                            });
-                            app.process_data.insert(
-                                (ProcessType::Recipe, name),
-                                ProcessData::Recipe {
-                                    crafting_machine: None,
-                                    modules: vec![],
-                                    beacons: Default::default(),
-                                },
-                            );
+                            app.model
+                                .processes
+                                .push(Process::new(ProcessType::Recipe, name));
                             app.menu_stack.pop();
                             app.menu_stack.pop();
                         },
@@ -276,16 +806,133 @@ impl App {
                             app.get_new_process_menu(Some(ProcessType::Recipe))
                         }),
                     },
+                    ProcessType::Generator => Menu {
+                        title: "Add Generator".into(),
+                        items: registry
+                            .generators
+                            .keys()
+                            .filter(|&name| {
+                                self.get_process_from_name(
+                                    &(Into::<String>::into(&ProcessType::Generator) + " :" + name),
+                                )
+                                .is_none()
+                            })
+                            .cloned()
+                            .collect::<Vec<String>>()
+                            .tap_mut(|vec| vec.sort()),
+                        handle_click: |app: &mut App, name: String| {
+                            app.model
+                                .processes
+                                .push(Process::new(ProcessType::Generator, name));
+                            app.menu_stack.pop();
+                            app.menu_stack.pop();
+                        },
+                        update_menu: Some(|app: &mut App| {
+                            app.get_new_process_menu(Some(ProcessType::Generator))
+                        }),
+                    },
+                    ProcessType::Research => Menu {
+                        title: "Add Research".into(),
+                        items: registry
+                            .technologies
+                            .values()
+                            .filter(|technology| {
+                                technology.unit.is_some()
+                                    && researched.as_ref().is_none_or(|researched| {
+                                        !researched.contains(&technology.name)
+                                    })
+                            })
+                            .map(|technology| &technology.name)
+                            .filter(|&name| {
+                                self.get_process_from_name(
+                                    &(Into::<String>::into(&ProcessType::Research) + " :" + name),
+                                )
+                                .is_none()
+                            })
+                            .cloned()
+                            .collect::<Vec<String>>()
+                            .tap_mut(|vec| vec.sort()),
+                        handle_click: |app: &mut App, name: String| {
+                            app.model
+                                .processes
+                                .push(Process::new(ProcessType::Research, name.clone()));
+                            let output = MaterialPrototype::Research(name);
+                            let amount = *app.model.outputs.entry(output.clone()).or_insert(0.0);
+                            let time_unit = app.model.get_time_unit(&output);
+                            app.selected_material = Some((output, amount));
+                            app.number_input = Some((
+                                "output_amount".into(),
+                                time_unit.convert(amount).to_string(),
+                            ));
+                            app.menu_stack.pop();
+                            app.menu_stack.pop();
+                        },
+                        update_menu: Some(|app: &mut App| {
+                            app.get_new_process_menu(Some(ProcessType::Research))
+                        }),
+                    },
+                    ProcessType::Void => {
+                        let bonuses = self.model.get_productivity_bonuses();
+                        Menu {
+                            title: "Add Void".into(),
+                            items: self
+                                .model
+                                .processes
+                                .iter()
+                                .flat_map(|process| {
+                                    process.get_net_amounts(surface.as_deref(), &bonuses)
+                                })
+                                .filter(|(material, amount)| {
+                                    *amount > 0.0
+                                        && matches!(
+                                            material,
+                                            MaterialPrototype::Item(..)
+                                                | MaterialPrototype::Fluid(_)
+                                        )
+                                })
+                                .map(|(material, _)| material.to_id())
+                                .collect::<HashSet<String>>()
+                                .into_iter()
+                                .filter(|name| {
+                                    self.get_process_from_name(
+                                        &(Into::<String>::into(&ProcessType::Void) + " :" + name),
+                                    )
+                                    .is_none()
+                                })
+                                .collect::<Vec<String>>()
+                                .tap_mut(|vec| vec.sort()),
+                            handle_click: |app: &mut App, name: String| {
+                                app.model
+                                    .processes
+                                    .push(Process::new(ProcessType::Void, name));
+                                app.menu_stack.pop();
+                                app.menu_stack.pop();
+                            },
+                            update_menu: Some(|app: &mut App| {
+                                app.get_new_process_menu(Some(ProcessType::Void))
+                            }),
+                        }
+                    }
                 }
             }
             None => Menu {
                 title: "Add Process".into(),
-                items: vec!["Recipe".into(), "Resource".into(), "Plant".into()],
+                items: vec![
+                    "Recipe".into(),
+                    "Resource".into(),
+                    "Plant".into(),
+                    "Generator".into(),
+                    "Research".into(),
+                    "Void".into(),
+                ],
                 handle_click: |app: &mut App, name: String| {
                     let menu = app.get_new_process_menu(Some(match name.as_str() {
                         "Resource" => ProcessType::Resource,
                         "Plant" => ProcessType::Plant,
                         "Recipe" => ProcessType::Recipe,
+                        "Generator" => ProcessType::Generator,
+                        "Research" => ProcessType::Research,
+                        "Void" => ProcessType::Void,
                         _ => panic!(),
                     }));
                     app.menu_stack.push((menu, 0));
@@ -297,12 +944,7 @@ impl App {
     fn get_processes_menu(&mut self) -> Menu {
         Menu {
             title: "Processes".into(),
-            items: self
-                .model
-                .processes
-                .iter()
-                .map(|process| Into::<String>::into(&process.process_type) + " :" + &*process.name)
-                .collect(),
+            items: self.model.processes.iter().map(get_process_label).collect(),
             handle_click: |app: &mut App, name: String| {
                 app.selected_process = name.into();
                 let menu = app.get_process_menu();
@@ -311,46 +953,59 @@ impl App {
             update_menu: Some(|app: &mut App| app.get_processes_menu()),
         }
     }
-    fn get_process_from_name(&mut self, name: &String) -> Option<&mut Process> {
-        self.model.processes.iter_mut().find(|process| {
-            (Into::<String>::into(&process.process_type) + " :" + &*process.name).as_str()
-                == name.as_str()
-        })
+    fn get_process_from_name(&mut self, name: &str) -> Option<&mut Process> {
+        self.model
+            .processes
+            .iter_mut()
+            .find(|process| get_process_label(process) == name)
     }
     fn get_selected_process(&mut self) -> &mut Process {
         self.get_process_from_name(&self.selected_process.as_ref().unwrap().clone())
             .unwrap()
     }
-    fn get_selected_process_data(&mut self) -> &mut ProcessData {
-        let process_type = self.get_selected_process().process_type;
-        let name = self.get_selected_process().name.clone();
-        self.process_data.get_mut(&(process_type, name)).unwrap()
-    }
     fn get_process_menu(&mut self) -> Menu {
-        if match self.get_selected_process_data() {
-            ProcessData::Resource {
-                mining_drill,
-                modules: _modules,
-                beacons: _beacons,
-            } => mining_drill.is_some(),
-            ProcessData::Recipe {
-                crafting_machine,
-                modules: _modules,
-                beacons: _beacons,
-            } => crafting_machine.is_some(),
-        } {
+        let process = self.get_selected_process();
+        if process.machine.is_some() || !needs_machine(process) {
             Menu {
-                title: self.selected_process.as_ref().unwrap().clone(),
-                items: if self.get_selected_process().process_type != ProcessType::Plant {
-                    vec![
+                title: match process.machine_limit {
+                    Some(limit) => format!(
+                        "{} ({} machines)",
+                        self.selected_process.as_ref().unwrap(),
+                        limit
+                    ),
+                    None => self.selected_process.as_ref().unwrap().clone(),
+                },
+                items: match self.get_selected_process().process_type {
+                    ProcessType::Resource | ProcessType::Research => vec![
                         "Machine".into(),
                         "Modules".into(),
                         "Beacons".into(),
+                        "Machine Count".into(),
+                        "Duplicate".into(),
+                        "Label".into(),
                         "Remove".into(),
-                    ]
-                } else {
-                    vec!["Remove".into()]
-                },
+                    ],
+                    ProcessType::Plant | ProcessType::Generator | ProcessType::Void => {
+                        vec!["Remove".into()]
+                    }
+                    ProcessType::Recipe => vec![
+                        "Machine".into(),
+                        "Modules".into(),
+                        "Beacons".into(),
+                        "Quality".into(),
+                        "Quality Loop".into(),
+                        "Unlocked By".into(),
+                        "Machine Count".into(),
+                        "Duplicate".into(),
+                        "Label".into(),
+                        "Remove".into(),
+                    ],
+                }
+                .tap_mut(|items| {
+                    if self.get_selected_process().get_fuel_categories().is_some() {
+                        items.insert(items.len() - 1, "Fuel".into());
+                    }
+                }),
                 handle_click: move |app: &mut App, name: String| match name.as_ref() {
                     "Machine" => {
                         let menu = app.get_machine_menu();
@@ -364,6 +1019,54 @@ impl App {
                         let menu = app.get_beacons_menu();
                         app.menu_stack.push((menu, 0));
                     }
+                    "Quality" => {
+                        let menu = app.get_quality_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Fuel" => {
+                        let menu = app.get_fuel_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Quality Loop" => {
+                        let menu = app.get_quality_loop_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Unlocked By" => {
+                        let registry = get_registry();
+                        let recipe = app.get_selected_process().name.clone();
+                        let technologies = get_unlocking_technologies(&registry, &recipe);
+                        app.set_message(
+                            if registry
+                                .recipes
+                                .get(&recipe)
+                                .is_some_and(|recipe| recipe.enabled)
+                            {
+                                format!("{} is available from the start", recipe)
+                            } else if technologies.is_empty() {
+                                format!("No technology unlocks {}", recipe)
+                            } else {
+                                format!("{} is unlocked by {}", recipe, technologies.join(", "))
+                            },
+                        );
+                    }
+                    "Machine Count" => {
+                        let menu = app.get_machine_limit_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Duplicate" => {
+                        let process = app.get_selected_process().clone();
+                        let label = (2..).map(|instance| instance.to_string()).find(|label| {
+                            !app.model.processes.contains(&Process {
+                                label: Some(label.clone()),
+                                ..process.clone()
+                            })
+                        });
+                        app.model.processes.push(Process { label, ..process });
+                    }
+                    "Label" => {
+                        let label = app.get_selected_process().label.clone().unwrap_or_default();
+                        app.text_input = Some(("process_label".into(), label));
+                    }
                     "Remove" => {
                         app.menu_stack.pop();
                         app.model.processes.remove(
@@ -371,9 +1074,7 @@ impl App {
                                 .processes
                                 .iter()
                                 .position(|process| {
-                                    (Into::<String>::into(&process.process_type)
-                                        + " :"
-                                        + &*process.name)
+                                    get_process_label(process)
                                         == *app.selected_process.as_ref().unwrap()
                                 })
                                 .unwrap(),
@@ -387,142 +1088,186 @@ impl App {
             self.get_machine_menu()
         }
     }
+    /// Labels the selected process, unless another instance of it has that label. An empty
+    /// label removes it.
+    fn set_process_label(&mut self, label: String) {
+        let process = self.get_selected_process().clone();
+        let relabelled = Process {
+            label: Some(label).filter(|label| !label.is_empty()),
+            ..process.clone()
+        };
+        if relabelled != process && self.model.processes.contains(&relabelled) {
+            self.set_message("Another instance already has this label!");
+            return;
+        }
+        self.selected_process = Some(get_process_label(&relabelled));
+        *self
+            .get_process_from_name(&get_process_label(&process))
+            .unwrap() = relabelled;
+    }
+    fn get_machine_limit_menu(&mut self) -> Menu {
+        Menu {
+            title: match self.get_selected_process().machine_limit {
+                Some(limit) => format!("Machine Count ({})", limit),
+                None => format!("Machine Count ({})", NO_LIMIT),
+            },
+            items: iter::once(NO_LIMIT.into())
+                .chain(LIMIT_KINDS.iter().map(|limit| limit.get_kind().into()))
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                app.menu_stack.pop();
+                let process = app.get_selected_process();
+                if name == NO_LIMIT {
+                    process.machine_limit = None;
+                    return;
+                }
+                let count = process.machine_limit.map_or(0.0, |limit| limit.get_value());
+                process.machine_limit = Some(Limit::try_from(&name).unwrap().with_value(count));
+                app.number_input = Some(("machine_limit".into(), count.to_string()));
+            },
+            update_menu: Some(|app: &mut App| app.get_machine_limit_menu()),
+        }
+    }
     fn get_machine_menu(&mut self) -> Menu {
         Menu {
             title: "Select Machine".into(),
             items: {
-                let registry = get_registry();
-                let process = self.get_selected_process();
-                match process.process_type {
-                    ProcessType::Resource => registry
-                        .mining_drills
-                        .iter()
-                        .filter_map(|(drill_name, drill)| {
-                            if drill
-                                .resource_categories
-                                .contains(&registry.resources.get(&process.name).unwrap().category)
-                            {
-                                drill_name.clone().into()
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                    ProcessType::Plant => panic!(),
-                    ProcessType::Recipe => registry
-                        .crafting_machines
-                        .iter()
-                        .filter_map(|(machine_name, machine)| {
-                            if machine
-                                .crafting_categories
-                                .contains(&registry.recipes.get(&process.name).unwrap().category)
-                            {
-                                machine_name.clone().into()
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                }
+                let surface = self.model.surface.clone();
+                self.get_selected_process()
+                    .get_machine_choices(surface.as_deref())
             },
             handle_click: |app: &mut App, name: String| {
-                let process_name = app.get_selected_process().name.clone();
-                match app.get_selected_process().process_type {
-                    ProcessType::Resource => {
-                        let ProcessData::Resource {
-                            mining_drill,
-                            modules: _modules,
-                            beacons: _beacons,
-                        } = app
-                            .process_data
-                            .get_mut(&(ProcessType::Resource, process_name))
-                            .unwrap()
-                        else {
-                            panic!()
-                        };
-                        *mining_drill = Option::from(name);
-                    }
-                    ProcessType::Plant => {
-                        panic!()
-                    }
-                    ProcessType::Recipe => {
-                        let ProcessData::Recipe {
-                            crafting_machine,
-                            modules: _modules,
-                            beacons: _beacons,
-                        } = app
-                            .process_data
-                            .get_mut(&(ProcessType::Recipe, process_name))
-                            .unwrap()
-                        else {
-                            panic!()
-                        };
-                        *crafting_machine = Option::from(name);
-                    }
-                }
+                app.get_selected_process().machine = Some(name);
                 app.menu_stack.pop();
             },
             update_menu: Some(|app: &mut App| app.get_machine_menu()),
         }
     }
+    fn get_quality_menu(&mut self) -> Menu {
+        Menu {
+            title: "Select Quality".into(),
+            items: {
+                let process = self.get_selected_process().clone();
+                get_quality_names(&get_registry())
+                    .into_iter()
+                    .filter(|quality| {
+                        !self.model.processes.contains(&Process {
+                            quality: quality.clone(),
+                            ..process.clone()
+                        })
+                    })
+                    .collect()
+            },
+            handle_click: |app: &mut App, name: String| {
+                let process = app.get_selected_process();
+                process.quality = name;
+                app.selected_process = Some(get_process_label(process));
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_quality_menu()),
+        }
+    }
+    fn get_fuel_menu(&mut self) -> Menu {
+        Menu {
+            title: "Select Fuel".into(),
+            items: {
+                let fuel_categories = self
+                    .get_selected_process()
+                    .get_fuel_categories()
+                    .unwrap_or_default();
+                get_registry()
+                    .items
+                    .values()
+                    .filter(|item| {
+                        item.fuel_value.is_some()
+                            && item
+                                .fuel_category
+                                .as_ref()
+                                .is_some_and(|category| fuel_categories.contains(category))
+                    })
+                    .map(|item| item.name.clone())
+                    .collect::<Vec<String>>()
+                    .tap_mut(|vec| vec.sort())
+            },
+            handle_click: |app: &mut App, name: String| {
+                app.get_selected_process().fuel = Some(name);
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_fuel_menu()),
+        }
+    }
+    fn get_quality_loop_menu(&mut self) -> Menu {
+        Menu {
+            title: "Recycle Up To".into(),
+            items: {
+                let process = self.get_selected_process();
+                get_quality_names(&get_registry())
+                    .into_iter()
+                    .skip_while(|quality| *quality != process.quality)
+                    .skip(1)
+                    .collect()
+            },
+            handle_click: |app: &mut App, name: String| {
+                let process = app.get_selected_process().clone();
+                app.menu_stack.pop();
+                match app.model.add_quality_loop(&process, &name) {
+                    Ok(()) => app.set_message(format!(
+                        "Added a quality loop up to {}, check the machines of the new recyclers",
+                        name
+                    )),
+                    Err(error) => app.set_message(error),
+                }
+            },
+            update_menu: None,
+        }
+    }
+    fn get_quality_loops_menu(&mut self) -> Menu {
+        Menu {
+            title: "Quality Loops (select to remove)".into(),
+            items: self
+                .model
+                .quality_loops
+                .iter()
+                .map(get_quality_loop_label)
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                app.model
+                    .quality_loops
+                    .retain(|quality_loop| get_quality_loop_label(quality_loop) != name);
+            },
+            update_menu: Some(|app: &mut App| app.get_quality_loops_menu()),
+        }
+    }
+    /// Returns the modules of the selected beacon, or of the selected process if no beacon
+    /// is selected.
+    fn get_selected_modules(&mut self) -> &mut Vec<String> {
+        match self.selected_beacon {
+            Some(_) => &mut self.get_selected_beacon().modules,
+            None => &mut self.get_selected_process().modules,
+        }
+    }
     fn get_modules_menu(&mut self) -> Menu {
         Menu {
             title: "Modules (select to remove)".into(),
             items: {
                 let registry = get_registry();
-                match self.selected_beacon.clone() {
-                    Some(beacon_name) => {
-                        let modules = &self.get_selected_beacon().modules;
-                        if modules.len()
-                            < registry.beacons.get(&beacon_name).unwrap().module_slots as usize
-                        {
-                            vec!["Add Module".into(), "Fill Modules".into()]
-                        } else {
-                            Vec::<String>::new()
-                        }
-                        .into_iter()
-                        .chain(modules.clone())
-                        .collect()
-                    }
-                    None => match self.get_selected_process_data() {
-                        ProcessData::Resource {
-                            mining_drill,
-                            modules,
-                            beacons: _beacons,
-                        } => if modules.len()
-                            < registry
-                                .mining_drills
-                                .get(mining_drill.as_ref().unwrap())
-                                .unwrap()
-                                .module_slots as usize
-                        {
-                            vec!["Add Module".into(), "Fill Modules".into()]
-                        } else {
-                            Vec::<String>::new()
-                        }
-                        .into_iter()
-                        .chain(modules.clone())
-                        .collect(),
-                        ProcessData::Recipe {
-                            crafting_machine,
-                            modules,
-                            beacons: _beacons,
-                        } => if modules.len()
-                            < registry
-                                .crafting_machines
-                                .get(crafting_machine.as_ref().unwrap())
-                                .unwrap()
-                                .module_slots as usize
-                        {
-                            vec!["Add Module".into(), "Fill Modules".into()]
-                        } else {
-                            Vec::<String>::new()
-                        }
-                        .into_iter()
-                        .chain(modules.clone())
-                        .collect(),
-                    },
+                let module_slots = match self.selected_beacon.as_ref() {
+                    Some(beacon_name) => registry.beacons.get(beacon_name).unwrap().module_slots,
+                    None => self
+                        .get_selected_process()
+                        .get_machine(&registry)
+                        .unwrap()
+                        .get_module_slots(),
+                };
+                let modules = self.get_selected_modules().clone();
+                if modules.len() < module_slots as usize {
+                    vec!["Add Module".into(), "Fill Modules".into()]
+                } else {
+                    Vec::<String>::new()
                 }
+                .into_iter()
+                .chain(modules)
+                .collect()
             },
             handle_click: |app: &mut App, name: String| match name.as_str() {
                 "Add Module" => {
@@ -535,37 +1280,13 @@ impl App {
                     let menu = app.get_add_module_menu();
                     app.menu_stack.push((menu, 0));
                 }
-                _ => match app.selected_beacon.clone() {
-                    Some(_) => {
-                        let modules = &mut app.get_selected_beacon().modules;
-                        modules
-                            .iter()
-                            .position(|string| *string == name)
-                            .map(|index| modules.remove(index));
-                    }
-                    None => match app.get_selected_process_data() {
-                        ProcessData::Resource {
-                            mining_drill: _mining_drill,
-                            modules,
-                            beacons: _beacons,
-                        } => {
-                            modules
-                                .iter()
-                                .position(|string| *string == name)
-                                .map(|index| modules.remove(index));
-                        }
-                        ProcessData::Recipe {
-                            crafting_machine: _crafting_machine,
-                            modules,
-                            beacons: _beacons,
-                        } => {
-                            modules
-                                .iter()
-                                .position(|string| *string == name)
-                                .map(|index| modules.remove(index));
-                        }
-                    },
-                },
+                _ => {
+                    let modules = app.get_selected_modules();
+                    modules
+                        .iter()
+                        .position(|string| *string == name)
+                        .map(|index| modules.remove(index));
+                }
             },
             update_menu: Some(|app: &mut App| app.get_modules_menu()),
         }
@@ -575,132 +1296,44 @@ impl App {
             title: "Add Module".into(),
             items: {
                 let registry = get_registry();
-                match self.selected_beacon.as_ref() {
+                let categories = match self.selected_beacon.as_ref() {
                     Some(beacon_name) => registry
-                        .modules
-                        .iter()
-                        .filter_map(|(name, module)| {
-                            let categories = &registry
-                                .beacons
-                                .get(beacon_name)
-                                .unwrap()
-                                .allowed_module_categories;
-                            if categories.is_empty() || categories.contains(&module.category) {
-                                Some(name.clone())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                    None => {
-                        let (process_name, process_type) = {
-                            let process = self.get_selected_process();
-                            (process.name.clone(), process.process_type)
-                        };
-                        match process_type {
-                            ProcessType::Resource => {
-                                let ProcessData::Resource {
-                                    mining_drill,
-                                    modules: _modules,
-                                    beacons: _beacons,
-                                } = self
-                                    .process_data
-                                    .get_mut(&(ProcessType::Resource, process_name))
-                                    .unwrap()
-                                else {
-                                    panic!()
-                                };
-                                registry
-                                    .modules
-                                    .iter()
-                                    .filter_map(|(name, module)| {
-                                        let categories = &registry
-                                            .mining_drills
-                                            .get(mining_drill.as_ref().unwrap())
-                                            .unwrap()
-                                            .allowed_module_categories;
-                                        if categories.is_empty()
-                                            || categories.contains(&module.category)
-                                        {
-                                            Some(name.clone())
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                    .collect()
-                            }
-                            ProcessType::Plant => panic!(),
-                            ProcessType::Recipe => {
-                                let &mut ProcessData::Recipe {
-                                    ref crafting_machine,
-                                    modules: _,
-                                    beacons: _,
-                                } = self
-                                    .process_data
-                                    .get_mut(&(ProcessType::Recipe, process_name))
-                                    .unwrap()
-                                else {
-                                    panic!()
-                                };
-                                registry
-                                    .modules
-                                    .iter()
-                                    .filter_map(|(name, module)| {
-                                        let categories = &registry
-                                            .crafting_machines
-                                            .get(crafting_machine.as_ref().unwrap())
-                                            .unwrap()
-                                            .allowed_module_categories;
-                                        if categories.is_empty()
-                                            || categories.contains(&module.category)
-                                        {
-                                            Some(name.clone())
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                    .collect()
-                            }
+                        .beacons
+                        .get(beacon_name)
+                        .unwrap()
+                        .allowed_module_categories
+                        .clone(),
+                    None => self
+                        .get_selected_process()
+                        .get_machine(&registry)
+                        .unwrap()
+                        .get_allowed_module_categories()
+                        .clone(),
+                };
+                registry
+                    .modules
+                    .iter()
+                    .filter_map(|(name, module)| {
+                        if categories.is_empty() || categories.contains(&module.category) {
+                            Some(name.clone())
+                        } else {
+                            None
                         }
-                    }
-                }
+                    })
+                    .collect()
             },
             handle_click: |app: &mut App, name: String| {
                 let fill = app.fill_modules;
-                let max_modules: u16;
                 let registry = get_registry();
-                let modules = match app.selected_beacon.as_ref() {
-                    Some(beacon_name) => {
-                        max_modules = registry.beacons.get(beacon_name).unwrap().module_slots;
-                        &mut app.get_selected_beacon().modules
-                    }
-                    None => match app.get_selected_process_data() {
-                        ProcessData::Resource {
-                            mining_drill,
-                            modules,
-                            beacons: _beacons,
-                        } => {
-                            max_modules = registry
-                                .mining_drills
-                                .get(mining_drill.as_ref().unwrap())
-                                .unwrap()
-                                .module_slots;
-                            modules
-                        }
-                        ProcessData::Recipe {
-                            crafting_machine,
-                            modules,
-                            beacons: _beacons,
-                        } => {
-                            max_modules = registry
-                                .crafting_machines
-                                .get(crafting_machine.as_ref().unwrap())
-                                .unwrap()
-                                .module_slots;
-                            modules
-                        }
-                    },
+                let max_modules: u16 = match app.selected_beacon.as_ref() {
+                    Some(beacon_name) => registry.beacons.get(beacon_name).unwrap().module_slots,
+                    None => app
+                        .get_selected_process()
+                        .get_machine(&registry)
+                        .unwrap()
+                        .get_module_slots(),
                 };
+                let modules = app.get_selected_modules();
                 for _ in 0..(if fill {
                     max_modules - modules.len() as u16
                 } else {
@@ -715,44 +1348,22 @@ impl App {
     }
     fn get_selected_beacon(&mut self) -> &mut Beacon {
         let beacon_name = self.selected_beacon.as_ref().unwrap().clone();
-        match self.get_selected_process_data() {
-            ProcessData::Resource {
-                mining_drill: _mining_drill,
-                modules: _modules,
-                beacons,
-            } => beacons,
-            ProcessData::Recipe {
-                crafting_machine: _crafting_machine,
-                modules: _modules,
-                beacons,
-            } => beacons,
-        }
-        .get_mut(&beacon_name)
-        .unwrap()
+        self.get_selected_process()
+            .beacons
+            .get_mut(&beacon_name)
+            .unwrap()
     }
     fn get_beacons_menu(&mut self) -> Menu {
         Menu {
             title: "Beacons".into(),
             items: vec!["Add Beacon".into()]
-                .into_iter()
-                .chain(match self.get_selected_process_data() {
-                    ProcessData::Resource {
-                        mining_drill: _mining_drill,
-                        modules: _modules,
-                        beacons,
-                    } => beacons
-                        .iter()
-                        .map(|(_name, beacon)| beacon.prototype.clone())
-                        .collect::<Vec<String>>(),
-                    ProcessData::Recipe {
-                        crafting_machine: _crafting_machine,
-                        modules: _modules,
-                        beacons,
-                    } => beacons
-                        .iter()
-                        .map(|(_name, beacon)| beacon.prototype.clone())
-                        .collect::<Vec<String>>(),
-                })
+                .into_iter()
+                .chain(
+                    self.get_selected_process()
+                        .beacons
+                        .values()
+                        .map(|beacon| beacon.prototype.clone()),
+                )
                 .collect(),
             handle_click: |app: &mut App, name: String| {
                 if name == "Add Beacon" {
@@ -782,19 +1393,7 @@ impl App {
                 }
                 "Remove" => {
                     let beacon = app.get_selected_beacon().prototype.clone();
-                    match app.get_selected_process_data() {
-                        ProcessData::Resource {
-                            mining_drill: _mining_drill,
-                            modules: _modules,
-                            beacons,
-                        } => beacons,
-                        ProcessData::Recipe {
-                            crafting_machine: _crafting_machine,
-                            modules: _modules,
-                            beacons,
-                        } => beacons,
-                    }
-                    .remove(&beacon);
+                    app.get_selected_process().beacons.remove(&beacon);
                     app.selected_beacon = None;
                     app.menu_stack.pop();
                 }
@@ -811,22 +1410,12 @@ impl App {
             title: "Add Beacon".into(),
             items: {
                 let registry = get_registry();
+                let beacons = &self.get_selected_process().beacons;
                 registry
                     .beacons
-                    .iter()
-                    .filter_map(|(name, _beacon)| {
-                        if match self.get_selected_process_data() {
-                            ProcessData::Resource {
-                                mining_drill: _mining_drill,
-                                modules: _modules,
-                                beacons,
-                            } => beacons.contains_key(name),
-                            ProcessData::Recipe {
-                                crafting_machine: _crafting_machine,
-                                modules: _modules,
-                                beacons,
-                            } => beacons.contains_key(name),
-                        } {
+                    .keys()
+                    .filter_map(|name| {
+                        if beacons.contains_key(name) {
                             None
                         } else {
                             Some(name.clone())
@@ -835,19 +1424,7 @@ impl App {
                     .collect()
             },
             handle_click: |app: &mut App, name: String| {
-                match app.get_selected_process_data() {
-                    ProcessData::Resource {
-                        mining_drill: _mining_drill,
-                        modules: _modules,
-                        beacons,
-                    } => beacons,
-                    ProcessData::Recipe {
-                        crafting_machine: _crafting_machine,
-                        modules: _modules,
-                        beacons,
-                    } => beacons,
-                }
-                .insert(
+                app.get_selected_process().beacons.insert(
                     name.clone(),
                     Beacon {
                         prototype: name,
@@ -865,8 +1442,14 @@ impl App {
             title: "Outputs".into(),
             items: vec!["Add Output".into()]
                 .into_iter()
-                .chain(self.model.outputs.iter().map(|(prototype, amount)| {
-                    prototype.to_id().clone() + ": " + &*amount.to_string()
+                .chain(self.model.outputs.iter().map(|(prototype, &amount)| {
+                    let time_unit = self.model.get_time_unit(prototype);
+                    prototype.to_id()
+                        + ": "
+                        + &match self.model.limits.get(prototype) {
+                            Some(limit) => limit.describe(time_unit),
+                            None => time_unit.format(amount),
+                        }
                 }))
                 .collect(),
             handle_click: |app: &mut App, name: String| {
@@ -874,10 +1457,8 @@ impl App {
                     let menu = app.get_new_output_menu();
                     app.menu_stack.push((menu, 0));
                 } else {
-                    let (material, amount) = name.split_once(": ").unwrap();
-                    let amount = amount.parse::<f64>().unwrap();
-                    app.selected_material =
-                        Some((app.model.get_output(material).unwrap().0, amount));
+                    let (material, _) = name.split_once(": ").unwrap();
+                    app.selected_material = app.model.get_output(material);
                     let menu = app.get_output_menu();
                     app.menu_stack.push((menu, 0));
                 }
@@ -890,8 +1471,14 @@ impl App {
             title: "Inputs".into(),
             items: vec!["Add Input".into()]
                 .into_iter()
-                .chain(self.model.inputs.iter().map(|(prototype, amount)| {
-                    prototype.to_id().clone() + ": " + &*amount.to_string()
+                .chain(self.model.inputs.iter().map(|(prototype, &amount)| {
+                    let time_unit = self.model.get_time_unit(prototype);
+                    prototype.to_id()
+                        + ": "
+                        + &match self.model.limits.get(prototype) {
+                            Some(limit) => limit.describe(time_unit),
+                            None => time_unit.format(amount),
+                        }
                 }))
                 .collect(),
             handle_click: |app: &mut App, name: String| {
@@ -899,10 +1486,8 @@ impl App {
                     let menu = app.get_new_input_menu();
                     app.menu_stack.push((menu, 0));
                 } else {
-                    let (material, amount) = name.split_once(": ").unwrap();
-                    let amount = amount.parse::<f64>().unwrap();
-                    app.selected_material =
-                        Some((app.model.get_input(material).unwrap().0, amount));
+                    let (material, _) = name.split_once(": ").unwrap();
+                    app.selected_material = app.model.get_input(material);
                     let menu = app.get_input_menu();
                     app.menu_stack.push((menu, 0));
                 }
@@ -914,15 +1499,39 @@ impl App {
         let (material, _current_amount) = self.selected_material.as_ref().unwrap();
         Menu {
             title: material.to_id(),
-            items: vec!["Edit Amount".into(), "Remove".into()],
+            items: vec![
+                "Edit Amount".into(),
+                "Edit Weight".into(),
+                "Limit".into(),
+                "Time Unit".into(),
+                "Remove".into(),
+            ],
             handle_click: |app: &mut App, name: String| {
                 let (material, current_amount) = app.selected_material.as_ref().unwrap();
-                let current_amount = current_amount.to_string();
+                let current_amount = app
+                    .model
+                    .get_time_unit(material)
+                    .convert(*current_amount)
+                    .to_string();
                 match name.as_str() {
                     "Edit Amount" => {
                         app.number_input = Some(("output_amount".into(), current_amount.clone()));
                     }
+                    "Limit" => {
+                        let menu = app.get_material_limit_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Time Unit" => {
+                        let menu = app.get_material_time_unit_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Edit Weight" => {
+                        let weight = app.model.get_output_weight(material).to_string();
+                        app.number_input = Some(("output_weight".into(), weight));
+                    }
                     "Remove" => {
+                        app.model.output_weights.remove(material);
+                        app.model.limits.remove(material);
                         app.model.outputs.remove(material);
                         app.menu_stack.pop();
                     }
@@ -936,15 +1545,33 @@ impl App {
         let (material, _current_amount) = self.selected_material.as_ref().unwrap();
         Menu {
             title: material.to_id(),
-            items: vec!["Edit Amount".into(), "Remove".into()],
+            items: vec![
+                "Edit Amount".into(),
+                "Limit".into(),
+                "Time Unit".into(),
+                "Remove".into(),
+            ],
             handle_click: |app: &mut App, name: String| {
                 let (material, current_amount) = app.selected_material.as_ref().unwrap();
-                let current_amount = current_amount.to_string();
+                let current_amount = app
+                    .model
+                    .get_time_unit(material)
+                    .convert(*current_amount)
+                    .to_string();
                 match name.as_str() {
                     "Edit Amount" => {
                         app.number_input = Some(("input_amount".into(), current_amount.clone()));
                     }
+                    "Limit" => {
+                        let menu = app.get_material_limit_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
+                    "Time Unit" => {
+                        let menu = app.get_material_time_unit_menu();
+                        app.menu_stack.push((menu, 0));
+                    }
                     "Remove" => {
+                        app.model.limits.remove(material);
                         app.model.inputs.remove(material);
                         app.menu_stack.pop();
                     }
@@ -954,24 +1581,149 @@ impl App {
             update_menu: Some(|app: &mut App| app.get_input_menu()),
         }
     }
+    fn get_ratios_menu(&mut self) -> Menu {
+        Menu {
+            title: "Output Ratios".into(),
+            items: iter::once(NEW_RATIO.into())
+                .chain(
+                    self.model
+                        .ratios
+                        .iter()
+                        .enumerate()
+                        .map(|(index, ratio)| format!("{}. {}", index + 1, ratio.get_label())),
+                )
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                if name == NEW_RATIO {
+                    app.model.ratios.push(Ratio::default());
+                    app.selected_ratio = Some(app.model.ratios.len() - 1);
+                } else {
+                    let (index, _) = name.split_once(". ").unwrap();
+                    app.selected_ratio = Some(index.parse::<usize>().unwrap() - 1);
+                }
+                let menu = app.get_ratio_menu();
+                app.menu_stack.push((menu, 0));
+            },
+            update_menu: Some(|app: &mut App| app.get_ratios_menu()),
+        }
+    }
+    fn get_selected_ratio(&mut self) -> &mut Ratio {
+        &mut self.model.ratios[self.selected_ratio.unwrap()]
+    }
+    fn get_ratio_menu(&mut self) -> Menu {
+        let ratio = self.get_selected_ratio().clone();
+        Menu {
+            title: format!("Ratio {}", ratio.get_label()),
+            items: ratio
+                .materials
+                .iter()
+                .map(|(material, share)| format!("{}: {}", material.to_id(), share))
+                .chain(["Add Material".into(), "Remove Ratio".into()])
+                .collect(),
+            handle_click: |app: &mut App, name: String| match name.as_str() {
+                "Add Material" => {
+                    let menu = app.get_new_ratio_material_menu();
+                    app.menu_stack.push((menu, 0));
+                }
+                "Remove Ratio" => {
+                    let index = app.selected_ratio.take().unwrap();
+                    app.model.ratios.remove(index);
+                    app.menu_stack.pop();
+                }
+                _ => {
+                    let (material, share) = name.split_once(": ").unwrap();
+                    app.selected_material =
+                        Some((MaterialPrototype::from_id(material).unwrap(), 0.0));
+                    app.number_input = Some(("ratio_share".into(), share.into()));
+                }
+            },
+            update_menu: Some(|app: &mut App| app.get_ratio_menu()),
+        }
+    }
+    fn get_new_ratio_material_menu(&mut self) -> Menu {
+        let ratio = self.get_selected_ratio().clone();
+        Menu {
+            title: "Add Material".into(),
+            items: self
+                .model
+                .outputs
+                .keys()
+                .chain(self.model.inputs.keys())
+                .filter(|material| !ratio.materials.iter().any(|(other, _)| other == *material))
+                .map(MaterialPrototype::to_id)
+                .collect::<HashSet<String>>()
+                .into_iter()
+                .collect::<Vec<String>>()
+                .tap_mut(|vec| vec.sort()),
+            handle_click: |app: &mut App, name: String| {
+                let material = MaterialPrototype::from_id(&name).unwrap();
+                app.get_selected_ratio()
+                    .materials
+                    .push((material.clone(), 1.0));
+                app.selected_material = Some((material, 0.0));
+                app.number_input = Some(("ratio_share".into(), "1".into()));
+                app.menu_stack.pop();
+            },
+            update_menu: Some(|app: &mut App| app.get_new_ratio_material_menu()),
+        }
+    }
+    fn get_material_limit_menu(&mut self) -> Menu {
+        let (material, _) = self.selected_material.as_ref().unwrap();
+        let time_unit = self.model.get_time_unit(material);
+        let limit = if self.model.outputs.contains_key(material) {
+            self.model.get_output_limit(material)
+        } else {
+            self.model.get_input_limit(material)
+        };
+        Menu {
+            title: format!("{} ({})", material.to_id(), limit.describe(time_unit)),
+            items: LIMIT_KINDS
+                .iter()
+                .map(|limit| limit.get_kind().into())
+                .collect(),
+            handle_click: |app: &mut App, name: String| {
+                app.menu_stack.pop();
+                let (material, amount) = app.selected_material.clone().unwrap();
+                let is_output = app.model.outputs.contains_key(&material);
+                let limit = Limit::try_from(&name).unwrap().with_value(amount);
+                // The default limits are those of the amount alone.
+                if matches!(
+                    (is_output, limit),
+                    (true, Limit::Minimum(_)) | (false, Limit::Maximum(_))
+                ) {
+                    app.model.limits.remove(&material);
+                } else {
+                    app.model.limits.insert(material.clone(), limit);
+                }
+                let amount = app.model.get_time_unit(&material).convert(amount);
+                let context = if is_output {
+                    "output_amount"
+                } else {
+                    "input_amount"
+                };
+                app.number_input = Some((context.into(), amount.to_string()));
+            },
+            update_menu: Some(|app: &mut App| app.get_material_limit_menu()),
+        }
+    }
     //noinspection DuplicatedCode
     fn get_new_output_menu(&mut self) -> Menu {
         Menu {
             title: "New Output".into(),
             items: {
                 let registry = get_registry();
-                iter::empty()
-                    .chain(
-                        registry
-                            .items
+                let qualities = get_quality_names(&registry);
+                iter::once(MaterialPrototype::Electricity)
+                    .chain(registry.items.keys().flat_map(|name| {
+                        qualities
                             .iter()
-                            .map(|(name, _prototype)| MaterialPrototype::Item(name.clone())),
-                    )
+                            .map(|quality| MaterialPrototype::Item(name.clone(), quality.clone()))
+                    }))
                     .chain(
                         registry
                             .fluids
-                            .iter()
-                            .map(|(name, _prototype)| MaterialPrototype::Fluid(name.clone())),
+                            .keys()
+                            .map(|name| MaterialPrototype::Fluid(name.clone())),
                     )
                     .filter_map(|prototype| match self.model.outputs.get(&prototype) {
                         Some(_) => None,
@@ -995,18 +1747,18 @@ impl App {
             title: "New Input".into(),
             items: {
                 let registry = get_registry();
-                iter::empty()
-                    .chain(
-                        registry
-                            .items
+                let qualities = get_quality_names(&registry);
+                iter::once(MaterialPrototype::Electricity)
+                    .chain(registry.items.keys().flat_map(|name| {
+                        qualities
                             .iter()
-                            .map(|(name, _prototype)| MaterialPrototype::Item(name.clone())),
-                    )
+                            .map(|quality| MaterialPrototype::Item(name.clone(), quality.clone()))
+                    }))
                     .chain(
                         registry
                             .fluids
-                            .iter()
-                            .map(|(name, _prototype)| MaterialPrototype::Fluid(name.clone())),
+                            .keys()
+                            .map(|name| MaterialPrototype::Fluid(name.clone())),
                     )
                     .filter_map(|prototype| match self.model.inputs.get(&prototype) {
                         Some(_) => None,
@@ -1025,300 +1777,287 @@ impl App {
         }
     }
 
-    fn get_effect_receiver(
-        process_data: &ProcessData,
-    ) -> (
-        EffectReceiver,
-        Vec<String>,
-        &Vec<String>,
-        &HashMap<String, Beacon>,
-    ) {
-        match process_data {
-            ProcessData::Resource {
-                mining_drill,
-                modules,
-                beacons,
-            } => (
-                get_registry()
-                    .mining_drills
-                    .get(mining_drill.as_ref().unwrap())
-                    .unwrap()
-                    .effect_receiver
-                    .unwrap_or_default(),
-                get_registry()
-                    .mining_drills
-                    .get(mining_drill.as_ref().unwrap())
-                    .unwrap()
-                    .allowed_effects
-                    .clone(),
-                modules,
-                beacons,
-            ),
-            ProcessData::Recipe {
-                crafting_machine,
-                modules,
-                beacons,
-            } => (
-                get_registry()
-                    .crafting_machines
-                    .get(crafting_machine.as_ref().unwrap())
-                    .unwrap()
-                    .effect_receiver
-                    .unwrap_or_default(),
-                get_registry()
-                    .crafting_machines
-                    .get(crafting_machine.as_ref().unwrap())
-                    .unwrap()
-                    .allowed_effects
-                    .clone(),
-                modules,
-                beacons,
-            ),
-        }
-    }
-
     //noinspection DuplicatedCode
-    fn solve_model(&mut self, generate_inputs: bool) {
-        let registry = get_registry();
+    fn solve_model(&mut self, mode: SolveMode) {
         // validate the project state
-        let invalid_processes: Vec<String> = self.model.processes.iter().filter_map(|process| {
-            if match self
-                .process_data
-                .get(&(process.process_type, process.name.clone()))
-                .unwrap()
-            {
-                ProcessData::Resource {
-                    mining_drill,
-                    modules: _modules,
-                    beacons: _beacons,
-                } => mining_drill,
-                ProcessData::Recipe {
-                    crafting_machine,
-                    modules: _modules,
-                    beacons: _beacons,
-                } => crafting_machine,
-            }
-            .is_none()
-            {
-                Some(process.name.clone())
-            } else {
-                None
-            }
-        }).collect();
+        let invalid_processes: Vec<String> = self
+            .model
+            .processes
+            .iter()
+            .filter_map(|process| {
+                if needs_machine(process) && process.machine.is_none() {
+                    Some(process.name.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
         if !invalid_processes.is_empty() {
-            self.set_message("Some processes don't have a machine!\n".to_owned() + &*invalid_processes.join(", "));
+            self.set_message(
+                "Some processes don't have a machine!\n".to_owned()
+                    + &*invalid_processes.join(", "),
+            );
             return;
         }
-        // update productivity values
-        for process in self.model.processes.iter_mut() {
-            let mut productivity = 0.0;
-            let (effect_receiver, allowed_effects, modules, beacons) = App::get_effect_receiver(
-                self.process_data
-                    .get(&(process.process_type, process.name.clone()))
-                    .unwrap(),
+        let processes_without_fuel: Vec<String> = self
+            .model
+            .processes
+            .iter()
+            .filter(|process| process.fuel.is_none() && process.get_fuel_categories().is_some())
+            .map(|process| process.name.clone())
+            .collect();
+        if !processes_without_fuel.is_empty() {
+            self.set_message(
+                "Some processes don't have a fuel!\n".to_owned()
+                    + &*processes_without_fuel.join(", "),
             );
-            if !allowed_effects.contains(&"productivity".into()) {
-                continue;
-            }
-            if let Some(base_effect) = effect_receiver.base_effect {
-                productivity += base_effect.productivity.unwrap_or(0.0);
-            }
-            if effect_receiver.uses_module_effects {
-                for module in modules.iter() {
-                    productivity += registry
-                        .modules
-                        .get(module)
-                        .unwrap()
-                        .effects
-                        .productivity
-                        .unwrap_or(0.0);
-                }
-            }
-            if effect_receiver.uses_beacon_effects {
-                for beacon in beacons.values() {
-                    let prototype = registry.beacons.get(&beacon.prototype).unwrap();
-                    for module in beacon.modules.iter() {
-                        productivity += registry
-                            .modules
-                            .get(module)
-                            .unwrap()
-                            .effects
-                            .productivity
-                            .unwrap_or(0.0)
-                            * (prototype.efficiency as f32)
-                            * prototype.profile.as_ref().map_or(1.0, |profile| {
-                                *profile
-                                    .get(if prototype.beacon_counter == Some("same_type".into()) {
-                                        beacon.count as usize
-                                    } else {
-                                        beacons.len()
-                                    })
-                                    .unwrap_or(profile.last().unwrap_or(&1.0))
-                                    as f32
-                            });
-                    }
-                }
-            }
-            // if effect_receiver.uses_surface_effects {
-            //     todo!()
-            // }
-            process.productivity = productivity;
+            return;
+        }
+        let processes_off_surface: Vec<String> = self
+            .model
+            .processes
+            .iter()
+            .filter(|process| !process.can_run_on(self.model.surface.as_deref()))
+            .map(|process| process.name.clone())
+            .collect();
+        if !processes_off_surface.is_empty() {
+            self.set_message(
+                "Some processes can't run on this surface!\n".to_owned()
+                    + &*processes_off_surface.join(", "),
+            );
+            return;
         }
-        let message: String = match self.model.solve(generate_inputs) {
-            ModelResult::NoSolution => "No Solution!".into(),
+        let message: String = match match mode {
+            SolveMode::Minimise => self.model.solve(false),
+            SolveMode::GenerateInputs => self.model.solve(true),
+            SolveMode::MaximiseOutputs => self.model.maximise(false),
+            SolveMode::MaximiseRatios => self.model.maximise(true),
+            SolveMode::WholeMachines(rounding) => self.model.solve_whole_machines(true, rounding),
+            SolveMode::Sensitivity => self.model.solve_with_sensitivity(false),
+        } {
+            ModelResult::NoSolution(infeasibilities) => iter::once("No Solution!".to_string())
+                .chain(infeasibilities.iter().map(|infeasibility| {
+                    infeasibility.describe(self.model.get_time_unit(infeasibility.get_material()))
+                }))
+                .collect::<Vec<String>>()
+                .join("\n"),
             ModelResult::Unbounded => "There's no limit on how much product can be made!\
             Try \"Solve Model - Generate inputs\""
                 .into(),
-            ModelResult::OneSolution(solution) => {
-                let registry = get_registry().clone();
-                vec!["Solution:".to_string()]
-                    .into_iter()
-                    .chain(solution.iter().map(|(process, &amount)| {
-                        let time: f64 = match process.process_type {
-                            ProcessType::Resource => {
-                                registry
-                                    .resources
-                                    .get(&process.name)
-                                    .unwrap()
-                                    .results
-                                    .mining_time
-                            }
-                            ProcessType::Plant => {
-                                let plant = registry.plants.get(&process.name).unwrap();
-                                (plant.growth_ticks / 60) as f64 + plant.results.mining_time
-                            }
-                            ProcessType::Recipe => {
-                                registry.recipes.get(&process.name).unwrap().energy_required
-                            }
-                        };
-                        let speed: f64 = match process.process_type {
-                            ProcessType::Resource => {
-                                let ProcessData::Resource {
-                                    mining_drill,
-                                    modules: _modules,
-                                    beacons: _beacons,
-                                } = self
-                                    .process_data
-                                    .get(&(process.process_type, process.name.clone()))
-                                    .unwrap()
-                                else {
-                                    panic!()
-                                };
-                                Some(
-                                    registry
-                                        .mining_drills
-                                        .get(mining_drill.as_ref().unwrap())
-                                        .unwrap()
-                                        .mining_speed,
-                                )
-                            }
-                            ProcessType::Plant => None,
-                            ProcessType::Recipe => {
-                                let ProcessData::Recipe {
-                                    crafting_machine,
-                                    modules: _modules,
-                                    beacons: _beacons,
-                                } = self
-                                    .process_data
-                                    .get(&(process.process_type, process.name.clone()))
-                                    .unwrap()
-                                else {
-                                    panic!()
-                                };
-                                Some(
-                                    registry
-                                        .crafting_machines
-                                        .get(crafting_machine.as_ref().unwrap())
-                                        .unwrap()
-                                        .crafting_speed,
-                                )
-                            }
-                        }
-                        .map_or(0.0, |mut speed| {
-                            let (effect_receiver, allowed_effects, modules, beacons) =
-                                App::get_effect_receiver(
-                                    self.process_data
-                                        .get(&(process.process_type, process.name.clone()))
-                                        .unwrap(),
-                                );
-
-                            if !allowed_effects.contains(&"speed".into()) {
-                                return speed;
-                            }
-                            if let Some(base_effect) = effect_receiver.base_effect {
-                                speed += base_effect.speed.unwrap_or(0.0) as f64;
-                            }
-                            if effect_receiver.uses_module_effects {
-                                for module in modules.iter() {
-                                    speed += registry
-                                        .modules
-                                        .get(module)
-                                        .unwrap()
-                                        .effects
-                                        .speed
-                                        .unwrap_or(0.0)
-                                        as f64
-                                }
-                            }
-                            if effect_receiver.uses_beacon_effects {
-                                for beacon in beacons.values() {
-                                    let prototype =
-                                        registry.beacons.get(&beacon.prototype).unwrap();
-                                    for module in beacon.modules.iter() {
-                                        speed += registry
-                                            .modules
-                                            .get(module)
-                                            .unwrap()
-                                            .effects
-                                            .speed
-                                            .unwrap_or(0.0)
-                                            as f64
-                                            * (prototype.efficiency)
-                                            * prototype.profile.as_ref().map_or(1.0, |profile| {
-                                                *profile
-                                                    .get(
-                                                        if prototype.beacon_counter
-                                                            == Some("same_type".into())
-                                                        {
-                                                            beacon.count as usize
-                                                        } else {
-                                                            beacons.len()
-                                                        },
-                                                    )
-                                                    .unwrap_or(profile.last().unwrap_or(&1.0))
-                                            });
-                                    }
-                                }
-                            }
-                            speed
-                        });
-                        format!("{} : {}", process.name, amount * time / speed)
-                    }))
-                    .collect::<Vec<String>>()
-                    .join("\n")
-            }
+            ModelResult::OneSolution(solution) => iter::once("Solution:".to_string())
+                .chain(self.format_solution(&solution, &self.model.outputs))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            ModelResult::WholeMachines {
+                solution,
+                overproduction,
+            } => iter::once("Solution with whole machines:".to_string())
+                .chain(overproduction.iter().map(|(material, amount)| {
+                    format!(
+                        "Overproduction {} : {}",
+                        material.to_id(),
+                        self.model.get_time_unit(material).format(*amount)
+                    )
+                }))
+                .chain(iter::once(String::new()))
+                .chain(self.format_solution(&solution, &self.model.outputs))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            ModelResult::Maximised {
+                solution,
+                outputs,
+                binding_inputs,
+            } => iter::once("Maximised outputs:".to_string())
+                .chain(
+                    outputs
+                        .iter()
+                        .map(|(material, amount)| {
+                            format!(
+                                "{} : {}",
+                                material.to_id(),
+                                self.model.get_time_unit(material).format(*amount)
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .tap_mut(|vec| vec.sort()),
+                )
+                .chain(iter::once(format!(
+                    "Limited by : {}",
+                    if binding_inputs.is_empty() {
+                        "no input".into()
+                    } else {
+                        binding_inputs
+                            .iter()
+                            .map(MaterialPrototype::to_id)
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    }
+                )))
+                .chain(iter::once("\nSolution:".to_string()))
+                .chain(self.format_solution(&solution, &outputs))
+                .collect::<Vec<String>>()
+                .join("\n"),
             ModelResult::MultipleSolutions {
                 lower_bounds,
                 higher_bounds,
             } => vec!["Solution:".to_string()]
                 .into_iter()
                 .chain(lower_bounds.iter().map(|(material, amount)| {
-                    format!("{} can be reduced to {}", material.to_id(), amount)
+                    format!(
+                        "{} can be reduced to {}",
+                        material.to_id(),
+                        self.model.get_time_unit(material).format(*amount)
+                    )
                 }))
                 .chain(higher_bounds.iter().map(|(material, amount)| {
-                    format!("{} can be increased to {}", material.to_id(), amount)
+                    format!(
+                        "{} can be increased to {}",
+                        material.to_id(),
+                        self.model.get_time_unit(material).format(*amount)
+                    )
                 }))
                 .collect::<Vec<String>>()
                 .join("\n"),
         };
         self.set_message(message);
     }
+    /// Describes the machines, emissions, surpluses and deficits, shadow prices, research and
+    /// quality loops of a solution making `outputs`.
+    fn format_solution(
+        &self,
+        solution: &Solution,
+        outputs: &HashMap<MaterialPrototype, f64>,
+    ) -> Vec<String> {
+        let surface = self.model.surface.as_deref();
+        let bonuses = self.model.get_productivity_bonuses();
+        solution
+            .processes
+            .iter()
+            .map(|process| {
+                format!(
+                    "{} : {} (speed {}, productivity {}%, consumption {}%, {} MW){}",
+                    get_process_label(&process.process),
+                    process.machines,
+                    process.speed,
+                    process.productivity * 100.0,
+                    process.consumption * 100.0,
+                    process.power,
+                    if process.emissions.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ({})", format_emissions(&process.emissions))
+                    }
+                )
+            })
+            .chain(iter::once(format!(
+                "Emissions per minute : {}",
+                format_emissions(&solution.get_emissions())
+            )))
+            .chain(
+                solution
+                    .get_imbalances(outputs)
+                    .into_iter()
+                    .map(|(material, amount)| {
+                        let time_unit = self.model.get_time_unit(&material);
+                        if amount > 0.0 {
+                            format!(
+                                "Surplus {} : {}",
+                                material.to_id(),
+                                time_unit.format(amount)
+                            )
+                        } else {
+                            format!(
+                                "Deficit {} : {}",
+                                material.to_id(),
+                                time_unit.format(-amount)
+                            )
+                        }
+                    }),
+            )
+            .chain(solution.sensitivity.iter().flat_map(|sensitivity| {
+                iter::once("\nShadow prices (objective saved per extra unit):".into())
+                    .chain(sensitivity.materials.iter().map(|material| {
+                        let time_unit = self.model.get_time_unit(&material.material);
+                        format!(
+                            "  {}{} : {} {}",
+                            if material.binding { "Bottleneck " } else { "" },
+                            material.material.to_id(),
+                            time_unit.to_per_second(material.shadow_price),
+                            String::from(&time_unit)
+                        )
+                    }))
+                    .chain(sensitivity.reduced_costs.iter().map(|(process, cost)| {
+                        format!(
+                            "  Unused {} : {} more per craft {}",
+                            get_process_label(process),
+                            self.model.time_unit.to_per_second(*cost),
+                            String::from(&self.model.time_unit)
+                        )
+                    }))
+                    .collect::<Vec<String>>()
+            }))
+            .chain(
+                get_research_reports(solution)
+                    .into_iter()
+                    .flat_map(|report| {
+                        let time_unit = self
+                            .model
+                            .get_time_unit(&MaterialPrototype::Research(report.technology.clone()));
+                        iter::once(format!(
+                            "\nResearch {} : {} units in {} labs",
+                            report.technology,
+                            time_unit.format(report.units_per_second),
+                            report.labs
+                        ))
+                        .chain(report.time.map(|time| format!("  Done in {} s", time)))
+                        .chain(report.packs_per_second.into_iter().map(|(pack, amount)| {
+                            format!(
+                                "  {} of {}",
+                                self.model.get_time_unit(&pack).format(amount),
+                                pack.to_id()
+                            )
+                        }))
+                        .collect::<Vec<String>>()
+                    }),
+            )
+            .chain(self.model.quality_loops.iter().flat_map(|quality_loop| {
+                let report = quality_loop.get_report(solution, surface, &bonuses);
+                iter::once(format!(
+                    "\n{} : {}",
+                    get_quality_loop_label(quality_loop),
+                    self.model
+                        .get_time_unit(&quality_loop.get_target())
+                        .format(report.output)
+                ))
+                .chain(
+                    report
+                        .output_per_input
+                        .iter()
+                        .map(|(material, amount)| format!("  {} per {}", amount, material.to_id())),
+                )
+                .chain([
+                    format!("  Assemblers : {}", report.assembler_machines),
+                    format!("  Recyclers : {}", report.recycler_machines),
+                ])
+                .chain(report.flows.iter().map(|(material, produced, consumed)| {
+                    let time_unit = self.model.get_time_unit(material);
+                    format!(
+                        "  {} : {} made, {} used",
+                        material.to_id(),
+                        time_unit.format(*produced),
+                        time_unit.format(*consumed)
+                    )
+                }))
+                .collect::<Vec<String>>()
+            }))
+            .collect()
+    }
     fn save_project(&mut self, force_prompt: bool) {
         let file = if !force_prompt && let Some(file) = self.save_path.clone() {
             file
         } else {
             let Some(file) = DialogBuilder::file()
-                .add_filter("Project files", &["cfpr"])
+                .add_filter("Project files", ["cfpr"])
                 .save_single_file()
                 .show()
                 .unwrap()
@@ -1332,13 +2071,6 @@ impl App {
         let save_data = SaveData {
             registry: (*get_registry()).clone(),
             model: self.model.clone(),
-            process_data: self
-                .process_data
-                .iter()
-                .map(|((process_type, name), value)| {
-                    ((process_type.clone(), name.clone()), value.clone())
-                })
-                .collect(),
         };
         if let Err(e) = fs::write(file, rmp_serde::to_vec(&save_data).unwrap()) {
             self.set_message(format!("Failed to save data! Error: {}", e));
@@ -1348,21 +2080,21 @@ impl App {
     }
     fn load_project(&mut self) {
         match DialogBuilder::file()
-            .add_filter("Project files", &["cfpr"])
+            .add_filter("Project files", ["cfpr"])
             .open_single_file()
             .show()
             .unwrap()
         {
             Some(file) => match fs::read(file) {
-                Ok(data) => match rmp_serde::from_slice::<SaveData>(&data) {
-                    Ok(SaveData {
-                        model,
-                        registry,
-                        process_data,
-                    }) => {
-                        set_registry(registry);
+                Ok(data) => match read_project(&data) {
+                    Ok((registry, model)) => {
+                        match registry {
+                            Some(registry) => set_registry(registry),
+                            None => self.set_message(
+                                "Converted a project from an older version, load its game data again",
+                            ),
+                        }
                         self.model = model;
-                        self.process_data = process_data;
                         let menu = self.get_project_menu();
                         self.menu_stack.push((menu, 0));
                     }
@@ -1379,6 +2111,18 @@ impl App {
             }
         }
     }
+    /// Sets the value of the limit of `material` to the `amount` entered, and asks for the
+    /// highest amount of a range.
+    fn set_limit_value(&mut self, material: MaterialPrototype, amount: f64) {
+        let Some(limit) = self.model.limits.get_mut(&material) else {
+            return;
+        };
+        *limit = limit.with_value(amount);
+        if let Limit::Range(_, highest) = *limit {
+            let highest = self.model.get_time_unit(&material).convert(highest);
+            self.number_input = Some(("limit_highest".into(), highest.to_string()));
+        }
+    }
     fn set_message<S: Into<String>>(&mut self, message: S) {
         self.message = Some(message.into());
         self.message_scroll = 0;
@@ -1393,154 +2137,267 @@ impl App {
         self.menu_stack.last_mut().unwrap().1 = index
     }
     fn handle_input(&mut self) -> Result<(), Box<dyn Error>> {
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    // Check if in beacon count input mode
-                    match key.code {
-                        KeyCode::Char(c) if let Some(query) = self.search_query.as_mut() => {
-                            query.push(c);
+        if event::poll(std::time::Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+        {
+            if key.kind == KeyEventKind::Press {
+                // Check if in beacon count input mode
+                match key.code {
+                    KeyCode::Char(c) if let Some(query) = self.search_query.as_mut() => {
+                        query.push(c);
+                    }
+                    KeyCode::Char(c) if let Some((_context, input)) = self.text_input.as_mut() => {
+                        input.push(c);
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                        if let Some((_context, input)) = self.number_input.as_mut() {
+                            input.push(c);
                         }
-                        KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
-                            if let Some((_context, input)) = self.number_input.as_mut() {
-                                input.push(c);
-                            }
+                    }
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.save_project(false);
+                    }
+                    KeyCode::Backspace => {
+                        if let Some((_context, input)) = self.number_input.as_mut() {
+                            input.pop();
                         }
-                        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.save_project(false);
+                        if let Some((_context, input)) = self.text_input.as_mut() {
+                            input.pop();
                         }
-                        KeyCode::Backspace => {
-                            if let Some((_context, input)) = self.number_input.as_mut() {
-                                input.pop();
-                            }
-                            if let Some(query) = self.search_query.as_mut() {
-                                query.pop();
+                        if let Some(query) = self.search_query.as_mut() {
+                            query.pop();
+                        }
+                    }
+                    KeyCode::Esc => {
+                        if self.search_query.is_some()
+                            || self.number_input.is_some()
+                            || self.text_input.is_some()
+                        {
+                            self.search_query = None;
+                            self.number_input = None;
+                            self.text_input = None;
+                        } else if self.message.is_some() {
+                            self.message = None;
+                        } else {
+                            self.menu_stack.pop();
+                            if self.menu_stack.is_empty() {
+                                self.should_quit = true;
+                                return Ok(());
                             }
                         }
-                        KeyCode::Esc => {
-                            if self.search_query.is_some() || self.number_input.is_some() {
-                                self.search_query = None;
-                                self.number_input = None;
-                            } else if self.message.is_some() {
-                                self.message = None;
-                            } else {
-                                self.menu_stack.pop();
-                                if self.menu_stack.is_empty() {
-                                    self.should_quit = true;
-                                    return Ok(())
-                                }
+                    }
+                    KeyCode::Up | KeyCode::Char('w') => {
+                        if self.message.is_none() {
+                            let selected = self.current_selected();
+                            if selected > 0 {
+                                self.set_selected(selected - 1)
                             }
                         }
-                        KeyCode::Up | KeyCode::Char('w') => {
-                            if self.message.is_none() {
-                                let selected = self.current_selected();
-                                if selected > 0 {
-                                    self.set_selected(selected - 1)
-                                }
+                    }
+                    KeyCode::Down | KeyCode::Char('s') => {
+                        if self.message.is_none() {
+                            let selected = self.current_selected();
+                            let menu = self.current_menu().unwrap();
+                            let items = get_displayed_list(menu, self);
+                            if !items.is_empty() && selected < items.len() - 1 {
+                                self.set_selected(selected + 1)
                             }
                         }
-                        KeyCode::Down | KeyCode::Char('s') => {
-                            if self.message.is_none() {
-                                let selected = self.current_selected();
-                                let menu = self.current_menu().unwrap();
-                                let items = get_displayed_list(menu, self);
-                                if items.len() > 0 && selected < items.len() - 1 {
-                                    self.set_selected(selected + 1)
-                                }
+                    }
+                    KeyCode::Enter | KeyCode::Char(' ') => {
+                        if let Some((context, input)) = self.text_input.take() {
+                            if context == "process_label" {
+                                self.set_process_label(input);
                             }
+                            return Ok(());
                         }
-                        KeyCode::Enter | KeyCode::Char(' ') => {
-                            if let Some((context, input)) = self.number_input.take() {
-                                match context.as_str() {
-                                    "beacon_count" => {
-                                        if let Ok(count) = input.parse::<u16>() {
-                                            self.get_selected_beacon().count = count;
+                        if let Some((context, input)) = self.number_input.take() {
+                            match context.as_str() {
+                                "machine_limit" => {
+                                    if let Ok(count) = input.parse::<f64>() {
+                                        let process = self.get_selected_process();
+                                        process.machine_limit = process
+                                            .machine_limit
+                                            .map(|limit| limit.with_value(count));
+                                        if let Some(Limit::Range(_, highest)) =
+                                            process.machine_limit
+                                        {
+                                            self.number_input = Some((
+                                                "machine_limit_highest".into(),
+                                                highest.to_string(),
+                                            ));
                                         }
                                     }
-                                    "output_amount" => {
-                                        if let Ok(amount) = input.parse::<f64>() {
-                                            let (material, _) =
-                                                self.selected_material.as_ref().unwrap().clone();
-                                            self.model.outputs.insert(material, amount);
+                                }
+                                "machine_limit_highest" => {
+                                    if let Ok(count) = input.parse::<f64>() {
+                                        let process = self.get_selected_process();
+                                        process.machine_limit = process
+                                            .machine_limit
+                                            .map(|limit| limit.with_highest(count));
+                                    }
+                                }
+                                "limit_highest" => {
+                                    if let Ok(amount) = input.parse::<f64>() {
+                                        let (material, _) =
+                                            self.selected_material.as_ref().unwrap().clone();
+                                        let amount = self
+                                            .model
+                                            .get_time_unit(&material)
+                                            .to_per_second(amount);
+                                        if let Some(limit) = self.model.limits.get_mut(&material) {
+                                            *limit = limit.with_highest(amount);
                                         }
                                     }
-                                    "input_amount" => {
-                                        if let Ok(amount) = input.parse::<f64>() {
-                                            let (material, _) =
-                                                self.selected_material.as_ref().unwrap().clone();
-                                            self.model.inputs.insert(material, amount);
+                                }
+                                "ratio_share" => {
+                                    if let Ok(share) = input.parse::<f64>() {
+                                        let (material, _) =
+                                            self.selected_material.as_ref().unwrap().clone();
+                                        let ratio = self.get_selected_ratio();
+                                        let index = ratio
+                                            .materials
+                                            .iter()
+                                            .position(|(other, _)| *other == material)
+                                            .unwrap();
+                                        // A share of 0 takes the material out of the ratio.
+                                        if share == 0.0 {
+                                            ratio.materials.remove(index);
+                                        } else {
+                                            ratio.materials[index].1 = share;
                                         }
                                     }
-                                    _ => {}
                                 }
-                                self.number_input = None;
-                                return Ok(());
-                            }
-                            if self.message.is_some() {
-                                self.message = None;
-                                self.scroll_key = None;
-                            } else {
-                                let (current_menu, selected) = self.menu_stack.last().unwrap();
-                                let function = current_menu.handle_click.clone();
-                                let items_to_display: Vec<&String> =
-                                    get_displayed_list(current_menu, self);
-                                if items_to_display.is_empty() {
-                                    return Ok(());
+                                "beacon_count" => {
+                                    if let Ok(count) = input.parse::<u16>() {
+                                        self.get_selected_beacon().count = count;
+                                    }
+                                }
+                                "output_amount" => {
+                                    if let Ok(amount) = input.parse::<f64>() {
+                                        let (material, _) =
+                                            self.selected_material.as_ref().unwrap().clone();
+                                        let amount = self
+                                            .model
+                                            .get_time_unit(&material)
+                                            .to_per_second(amount);
+                                        self.selected_material = Some((material.clone(), amount));
+                                        self.model.outputs.insert(material.clone(), amount);
+                                        self.set_limit_value(material, amount);
+                                    }
+                                }
+                                "input_amount" => {
+                                    if let Ok(amount) = input.parse::<f64>() {
+                                        let (material, _) =
+                                            self.selected_material.as_ref().unwrap().clone();
+                                        let amount = self
+                                            .model
+                                            .get_time_unit(&material)
+                                            .to_per_second(amount);
+                                        self.selected_material = Some((material.clone(), amount));
+                                        self.model.inputs.insert(material.clone(), amount);
+                                        self.set_limit_value(material, amount);
+                                    }
+                                }
+                                "output_weight" => {
+                                    if let Ok(weight) = input.parse::<f64>() {
+                                        let (material, _) =
+                                            self.selected_material.as_ref().unwrap().clone();
+                                        self.model.output_weights.insert(material, weight);
+                                    }
+                                }
+                                "material_cost" => {
+                                    if let Ok(cost) = input.parse::<f64>() {
+                                        let (material, _) =
+                                            self.selected_material.as_ref().unwrap().clone();
+                                        if cost == 0.0 {
+                                            self.model.costs.remove(&material);
+                                        } else {
+                                            self.model.costs.insert(material, cost);
+                                        }
+                                    }
                                 }
-                                if let Some(_query) = &self.search_query {
-                                    self.search_query = None;
+                                "research_level" => {
+                                    if let Ok(level) = input.parse::<u32>() {
+                                        let technology = self.selected_technology.take().unwrap();
+                                        if level == 0 {
+                                            self.model.research_levels.remove(&technology);
+                                        } else {
+                                            self.model.research_levels.insert(technology, level);
+                                        }
+                                    }
                                 }
-                                function(self, items_to_display[*selected].clone());
+                                _ => {}
                             }
+                            self.number_input = None;
+                            return Ok(());
                         }
-                        KeyCode::Char('f') | KeyCode::Char('F') => {
-                            self.search_query = Some(String::new());
+                        if self.message.is_some() {
+                            self.message = None;
+                            self.scroll_key = None;
+                        } else {
+                            let (current_menu, selected) = self.menu_stack.last().unwrap();
+                            let function = current_menu.handle_click;
+                            let items_to_display: Vec<&String> =
+                                get_displayed_list(current_menu, self);
+                            if items_to_display.is_empty() {
+                                return Ok(());
+                            }
+                            if let Some(_query) = &self.search_query {
+                                self.search_query = None;
+                            }
+                            function(self, items_to_display[*selected].clone());
                         }
-                        _ => {}
                     }
-                    let Some(current_menu) = self.current_menu() else {
-                        self.should_quit = true;
-                        return Ok(());
-                    };
-                    if let Some(update_menu) = current_menu.update_menu.clone() {
-                        let Some((_, pos)) = self.menu_stack.pop() else {
-                            panic!()
-                        };
-                        let new_menu = update_menu(self);
-                        self.menu_stack.push((new_menu, pos));
-                    }
-                    let mut length = get_displayed_list(self.current_menu().unwrap(), self).len();
-                    if length == 0 {
-                        length = 1;
-                    }
-                    let (_, pos) = self.menu_stack.last_mut().unwrap();
-                    if *pos >= length {
-                        *pos = length - 1
+                    KeyCode::Char('f') | KeyCode::Char('F') => {
+                        self.search_query = Some(String::new());
                     }
+                    _ => {}
                 }
+                let Some(current_menu) = self.current_menu() else {
+                    self.should_quit = true;
+                    return Ok(());
+                };
+                if let Some(update_menu) = current_menu.update_menu {
+                    let Some((_, pos)) = self.menu_stack.pop() else {
+                        panic!()
+                    };
+                    let new_menu = update_menu(self);
+                    self.menu_stack.push((new_menu, pos));
+                }
+                let mut length = get_displayed_list(self.current_menu().unwrap(), self).len();
+                if length == 0 {
+                    length = 1;
+                }
+                let (_, pos) = self.menu_stack.last_mut().unwrap();
+                if *pos >= length {
+                    *pos = length - 1
+                }
+            }
 
-                if let Some(_) = &self.message && key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown => {
-                            // Start tracking key press
-                            self.scroll_key = Some(key.code);
-                            self.scroll_start_time = Some(std::time::Instant::now());
-                            self.last_scroll_time = Some(std::time::Instant::now());
+            if let Some(_) = &self.message
+                && key.kind == KeyEventKind::Press
+            {
+                match key.code {
+                    KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown => {
+                        // Start tracking key press
+                        self.scroll_key = Some(key.code);
+                        self.scroll_start_time = Some(std::time::Instant::now());
+                        self.last_scroll_time = Some(std::time::Instant::now());
 
-                            // Handle initial scroll
-                            self.handle_scroll_key(key.code);
-                        }
-                        _ => {
-                            // Reset scroll tracking for non-scroll keys
-                            self.scroll_key = None;
-                        }
+                        // Handle initial scroll
+                        self.handle_scroll_key(key.code);
                     }
-                } else if key.kind == KeyEventKind::Release {
-                    // Clear scroll tracking when key is released
-                    if self.scroll_key == Some(key.code) {
+                    _ => {
+                        // Reset scroll tracking for non-scroll keys
                         self.scroll_key = None;
                     }
                 }
+            } else if key.kind == KeyEventKind::Release {
+                // Clear scroll tracking when key is released
+                if self.scroll_key == Some(key.code) {
+                    self.scroll_key = None;
+                }
             }
         }
         // Handle auto-repeat for held keys
@@ -1611,6 +2468,46 @@ fn restore_terminal() -> Result<(), Box<dyn Error>> {
     crossterm::execute!(std::io::stdout(), crossterm::terminal::LeaveAlternateScreen)?;
     Ok(())
 }
+fn get_process_label(process: &Process) -> String {
+    let mut label = Into::<String>::into(&process.process_type) + " :" + &*process.name;
+    if process.quality != NORMAL_QUALITY {
+        label = format!("{} ({})", label, process.quality);
+    }
+    match &process.label {
+        Some(instance) => format!("{} [{}]", label, instance),
+        None => label,
+    }
+}
+
+fn format_emissions(emissions: &HashMap<String, f64>) -> String {
+    let mut emissions: Vec<String> = emissions
+        .iter()
+        .map(|(pollutant, amount)| format!("{} {}/min", pollutant, amount))
+        .collect();
+    emissions.sort();
+    emissions.join(", ")
+}
+
+/// Returns whether the process runs in a machine the user has to choose.
+fn needs_machine(process: &Process) -> bool {
+    !matches!(
+        process.process_type,
+        ProcessType::Plant | ProcessType::Generator | ProcessType::Void
+    )
+}
+
+fn get_checkbox_label(name: &str, checked: bool) -> String {
+    format!("[{}] {}", if checked { "x" } else { " " }, name)
+}
+
+fn get_quality_loop_label(quality_loop: &QualityLoop) -> String {
+    format!(
+        "{} -> {}",
+        quality_loop.recipe,
+        quality_loop.get_target().to_id()
+    )
+}
+
 fn get_displayed_list<'a>(current_menu: &'a Menu, app: &App) -> Vec<&'a String> {
     if let Some(ref query) = app.search_query {
         current_menu
@@ -1665,6 +2562,8 @@ fn ui(frame: &mut Frame, app: &mut App) {
         format!("{} [Search: {}]", current_menu.title, query)
     } else if let Some((ref _context, ref input)) = app.number_input {
         format!("{} [Value: {}]", current_menu.title, input)
+    } else if let Some((ref _context, ref input)) = app.text_input {
+        format!("{} [Text: {}]", current_menu.title, input)
     } else {
         current_menu.title.clone()
     };
@@ -1742,7 +2641,7 @@ fn ui(frame: &mut Frame, app: &mut App) {
 
         // Add scroll indicator if needed
         if lines.len() > height {
-            let scroll_text = format!("{}/{}", scroll + 1, lines.len()-max_height+1);
+            let scroll_text = format!("{}/{}", scroll + 1, lines.len() - max_height + 1);
             let indicator = Paragraph::new(scroll_text)
                 .alignment(Alignment::Right)
                 .style(Style::default().fg(Color::DarkGray));
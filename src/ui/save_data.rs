@@ -0,0 +1,211 @@
+use crate::data::materials::MaterialPrototype;
+use crate::data::quality::NORMAL_QUALITY;
+use crate::data::{Beacon, Process, ProcessType, Registry};
+use crate::model::Model;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A project file: the game data and the model.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub registry: Registry,
+    pub model: Model,
+}
+
+/// A project file saved before processes carried their machine, modules and beacons. Its
+/// game data can't be read anymore and is skipped.
+#[derive(Deserialize)]
+struct LegacySaveData {
+    _registry: IgnoredAny,
+    model: LegacyModel,
+    process_data: HashMap<(ProcessType, String), LegacyProcessData>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LegacyModel {
+    processes: Vec<LegacyProcess>,
+    inputs: HashMap<LegacyMaterialPrototype, f64>,
+    outputs: HashMap<LegacyMaterialPrototype, f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LegacyProcess {
+    process_type: ProcessType,
+    name: String,
+    productivity: f32,
+}
+
+/// A material before items had a quality.
+#[derive(PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum LegacyMaterialPrototype {
+    Item(String),
+    Fluid(String),
+}
+
+#[derive(Serialize, Deserialize)]
+enum LegacyProcessData {
+    Resource {
+        mining_drill: Option<String>,
+        modules: Vec<String>,
+        beacons: HashMap<String, Beacon>,
+    },
+    Recipe {
+        crafting_machine: Option<String>,
+        modules: Vec<String>,
+        beacons: HashMap<String, Beacon>,
+    },
+}
+
+impl From<LegacyMaterialPrototype> for MaterialPrototype {
+    fn from(material: LegacyMaterialPrototype) -> Self {
+        match material {
+            LegacyMaterialPrototype::Item(name) => {
+                MaterialPrototype::Item(name, NORMAL_QUALITY.into())
+            }
+            LegacyMaterialPrototype::Fluid(name) => MaterialPrototype::Fluid(name),
+        }
+    }
+}
+
+impl LegacySaveData {
+    fn into_model(mut self) -> Model {
+        let materials = |amounts: HashMap<LegacyMaterialPrototype, f64>| {
+            amounts
+                .into_iter()
+                .map(|(material, amount)| (material.into(), amount))
+                .collect()
+        };
+        Model {
+            processes: self
+                .model
+                .processes
+                .into_iter()
+                .map(|process| {
+                    let new = Process::new(process.process_type, process.name.clone());
+                    match self
+                        .process_data
+                        .remove(&(process.process_type, process.name))
+                    {
+                        Some(
+                            LegacyProcessData::Resource {
+                                mining_drill: machine,
+                                modules,
+                                beacons,
+                            }
+                            | LegacyProcessData::Recipe {
+                                crafting_machine: machine,
+                                modules,
+                                beacons,
+                            },
+                        ) => Process {
+                            machine,
+                            modules,
+                            beacons,
+                            ..new
+                        },
+                        None => new,
+                    }
+                })
+                .collect(),
+            inputs: materials(self.model.inputs),
+            outputs: materials(self.model.outputs),
+            ..Default::default()
+        }
+    }
+}
+
+/// Reads a project file. Files saved before processes carried their machine are converted,
+/// without their game data: the registry is then `None`.
+pub fn read_project(data: &[u8]) -> Result<(Option<Registry>, Model), rmp_serde::decode::Error> {
+    match rmp_serde::from_slice::<SaveData>(data) {
+        Ok(SaveData { registry, model }) => Ok((Some(registry), model)),
+        Err(error) => rmp_serde::from_slice::<LegacySaveData>(data)
+            .map(|legacy| (None, legacy.into_model()))
+            .map_err(|_| error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::materials::MaterialPrototype;
+    use crate::data::{Beacon, Process, ProcessType};
+    use crate::model::limit::Limit;
+    use crate::ui::save_data::{
+        read_project, LegacyMaterialPrototype, LegacyModel, LegacyProcess, LegacyProcessData,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn reads_legacy_projects() {
+        let model = LegacyModel {
+            processes: vec![LegacyProcess {
+                process_type: ProcessType::Recipe,
+                name: "iron-gear-wheel".into(),
+                productivity: 0.0,
+            }],
+            inputs: HashMap::from([(LegacyMaterialPrototype::Item("iron-plate".into()), 2.0)]),
+            outputs: HashMap::from([(LegacyMaterialPrototype::Fluid("steam".into()), 1.0)]),
+        };
+        let process_data = HashMap::from([(
+            (ProcessType::Recipe, "iron-gear-wheel".to_string()),
+            LegacyProcessData::Recipe {
+                crafting_machine: Some("assembling-machine-2".into()),
+                modules: vec!["speed-module".into()],
+                beacons: HashMap::from([(
+                    "beacon".to_string(),
+                    Beacon {
+                        prototype: "beacon".into(),
+                        count: 2,
+                        modules: vec![],
+                    },
+                )]),
+            },
+        )]);
+        // the registry of the time, skipped
+        let data = rmp_serde::to_vec(&((), model, process_data)).unwrap();
+        let (registry, model) = read_project(&data).unwrap();
+        assert!(registry.is_none());
+        let process = &model.processes[0];
+        assert_eq!(process.machine.as_deref(), Some("assembling-machine-2"));
+        assert_eq!(process.modules, ["speed-module"]);
+        assert_eq!(process.beacons["beacon"].count, 2);
+        assert_eq!(
+            model.inputs[&MaterialPrototype::Item("iron-plate".into(), "normal".into())],
+            2.0
+        );
+        assert_eq!(
+            model.outputs[&MaterialPrototype::Fluid("steam".into())],
+            1.0
+        );
+        assert!(read_project(&[0xc0]).is_err());
+    }
+
+    #[test]
+    fn reads_fixed_machine_counts() {
+        // a process saved when its machine count could only be fixed
+        let data = rmp_serde::to_vec(&(
+            ProcessType::Recipe,
+            "iron-gear-wheel",
+            "normal",
+            Some("assembling-machine-2"),
+            Vec::<String>::new(),
+            HashMap::<String, Beacon>::new(),
+            None::<String>,
+            Some("2"),
+            Some(30.0),
+        ))
+        .unwrap();
+        let process: Process = rmp_serde::from_slice(&data).unwrap();
+        assert_eq!(process.label.as_deref(), Some("2"));
+        assert_eq!(process.machine_limit, Some(Limit::Exact(30.0)));
+
+        let process = Process {
+            machine_limit: Some(Limit::Range(1.0, 2.0)),
+            ..Process::new(ProcessType::Recipe, "iron-gear-wheel".into())
+        };
+        let data = rmp_serde::to_vec(&process).unwrap();
+        let process: Process = rmp_serde::from_slice(&data).unwrap();
+        assert_eq!(process.machine_limit, Some(Limit::Range(1.0, 2.0)));
+    }
+}